@@ -0,0 +1,68 @@
+//! Tests which must pass under every Anchor version supported by vipers.
+//!
+//! CI runs these once per version, after pinning it via `cargo update --precise`.
+#![cfg(test)]
+
+use anchor_lang::prelude::*;
+use vipers::*;
+
+#[error_code]
+pub enum ErrorCode {
+    MyError,
+    MyOtherError,
+}
+
+#[test]
+fn test_invariant_cmp() {
+    let (amount, max): (u64, u64) = (10, 100);
+    assert_does_not_throw!({
+        invariant_eq!(amount, 10);
+        invariant_neq!(amount, max, MyError);
+        invariant_gt!(max, amount, ErrorCode::MyError);
+        invariant_gte!(amount, 10, "at least ten");
+        invariant_lt!(amount, max, MyError, "below max");
+        invariant_lte!(amount, max, ErrorCode::MyError, "at most max");
+    });
+    assert_throws!(
+        {
+            invariant_gt!(amount, max);
+        },
+        VipersError::InvariantFailed
+    );
+    assert_throws!(
+        {
+            invariant_lte!(max, amount, "too large");
+        },
+        VipersError::InvariantFailed
+    );
+    assert_throws!(
+        {
+            invariant_eq!(amount, max, MyError);
+        },
+        ErrorCode::MyError
+    );
+    assert_throws!(
+        {
+            invariant_neq!(amount, 10, ErrorCode::MyOtherError, "must differ");
+        },
+        ErrorCode::MyOtherError
+    );
+
+    let (result, logs) = vipers::testing::capture_logs(|| -> Result<()> {
+        invariant_gte!(amount + 1, max / 2, MyError, "not enough");
+        Ok(())
+    });
+    assert_eq!(
+        result.into_cmp_error(),
+        error!(ErrorCode::MyError).into_cmp_error()
+    );
+    assert_eq!(
+        logs[..4],
+        [
+            "Program log: not enough",
+            "Program log: amount + 1 >= max / 2",
+            "Program log: Left: 11",
+            "Program log: Right: 50",
+        ]
+    );
+}
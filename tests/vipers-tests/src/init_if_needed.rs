@@ -0,0 +1,86 @@
+//! Example of guarding an `init_if_needed` account with [assert_init_if_needed_consistent].
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use vipers::*;
+
+#[derive(Accounts)]
+pub struct InitVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub owner: SystemAccount<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"vault", owner.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = owner,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Initializes the vault if needed, then checks that it holds the expected state.
+pub fn init_vault(ctx: Context<InitVault>) -> Result<()> {
+    check_vault(&ctx.accounts.vault, &ctx.accounts.owner, &ctx.accounts.mint)
+}
+
+fn check_vault(vault: &TokenAccount, owner: &impl AsKeyRef, mint: &impl AsKeyRef) -> Result<()> {
+    assert_init_if_needed_consistent!(vault, *owner, *mint);
+    Ok(())
+}
+
+/// Simulates a vault which already existed before the instruction.
+fn with_existing_vault(
+    owner: Pubkey,
+    mint: Pubkey,
+    f: impl FnOnce(&Account<TokenAccount>) -> Result<()>,
+) -> Result<()> {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        owner,
+        mint,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    vipers::testing::with_account(&anchor_spl::token::ID, data, |info| {
+        f(&Account::try_from(info)?)
+    })
+}
+
+#[test]
+fn test_existing_vault_consistent() {
+    let owner = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    assert!(with_existing_vault(owner, mint, |vault| check_vault(vault, &owner, &mint)).is_ok());
+}
+
+#[test]
+fn test_existing_vault_wrong_owner() {
+    let owner = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let attacker = Pubkey::new_unique();
+    assert_eq!(
+        with_existing_vault(attacker, mint, |vault| check_vault(vault, &owner, &mint))
+            .into_cmp_error(),
+        error!(VipersError::KeyMismatch).into_cmp_error()
+    );
+}
+
+#[test]
+fn test_existing_vault_wrong_mint() {
+    let owner = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let other_mint = Pubkey::new_unique();
+    assert_eq!(
+        with_existing_vault(owner, other_mint, |vault| check_vault(vault, &owner, &mint))
+            .into_cmp_error(),
+        error!(VipersError::MintMismatch).into_cmp_error()
+    );
+}
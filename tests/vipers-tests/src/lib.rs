@@ -1,6 +1,9 @@
 //! Vipers tests.
 #![cfg(test)]
 
+pub mod init_if_needed;
+pub mod pausable;
+
 use std::collections::BTreeMap;
 
 use anchor_lang::prelude::*;
@@ -90,6 +93,55 @@ fn test_unwrap_checked() -> Result<()> {
     Ok(())
 }
 
+#[error_code]
+pub enum StakeError {
+    MathOverflow,
+    Unknown,
+}
+
+impl From<VipersError> for StakeError {
+    fn from(err: VipersError) -> Self {
+        match err {
+            VipersError::IntegerOverflow => StakeError::MathOverflow,
+            _ => StakeError::Unknown,
+        }
+    }
+}
+
+#[test]
+fn test_remapped_math_errors() {
+    let (a, b): (u64, u64) = (1, 2);
+    assert_throws!(
+        {
+            unwrap_int!(a.checked_sub(b) => MyOtherError);
+        },
+        ErrorCode::MyOtherError
+    );
+    assert_throws!(
+        {
+            unwrap_int!(a.checked_sub(b) => StakeError::from(VipersError::IntegerOverflow));
+        },
+        StakeError::MathOverflow
+    );
+    assert_throws!(
+        {
+            unwrap_checked!({ a.checked_sub(b) } => StakeError::MathOverflow);
+        },
+        StakeError::MathOverflow
+    );
+    assert_does_not_throw!({
+        assert_eq!(unwrap_int!(b.checked_sub(a) => StakeError::MathOverflow), 1);
+    });
+
+    // clients see the custom code of the program, not that of VipersError::IntegerOverflow
+    let result =
+        || -> Result<u64> { Ok(unwrap_int!(a.checked_sub(b) => StakeError::MathOverflow)) };
+    let program_error: ProgramError = result().unwrap_err().into();
+    assert_eq!(program_error, ProgramError::Custom(6000));
+    let program_error: ProgramError = error!(VipersError::IntegerOverflow).into();
+    assert_eq!(program_error, ProgramError::Custom(1103));
+}
+
 #[test]
 fn test_unwrap_opt_block() {
     assert_throws!(
@@ -285,3 +337,948 @@ fn test_unwrap_bump() {
         VipersError::UnknownBump
     );
 }
+
+fn throw_in_match_arm(opt: Option<u64>) -> Result<u64> {
+    let value = match opt {
+        Some(v) => v,
+        None => throw_err!(MyError),
+    };
+    Ok(value)
+}
+
+fn throw_in_else(value: u64) -> Result<u64> {
+    let checked = if value < 10 {
+        value
+    } else {
+        throw_err!(ErrorCode::MyOtherError)
+    };
+    Ok(checked)
+}
+
+#[test]
+fn test_throw_err_expression_position() {
+    assert_eq!(throw_in_match_arm(Some(1)).unwrap(), 1);
+    assert_eq!(
+        throw_in_match_arm(None).into_cmp_error(),
+        error!(ErrorCode::MyError).into_cmp_error()
+    );
+    assert_eq!(throw_in_else(1).unwrap(), 1);
+    assert_eq!(
+        throw_in_else(10).into_cmp_error(),
+        error!(ErrorCode::MyOtherError).into_cmp_error()
+    );
+}
+
+#[test]
+fn test_throw_err_closure_tail() {
+    let always_throws = || -> Result<u64> { throw_err!(MyError) };
+    assert_eq!(
+        always_throws().into_cmp_error(),
+        error!(ErrorCode::MyError).into_cmp_error()
+    );
+}
+
+#[derive(Accounts)]
+struct Withdraw<'info> {
+    vault: AccountInfo<'info>,
+}
+
+struct WithdrawArgs {
+    amount: u64,
+}
+
+impl<'info> Validate<'info> for Withdraw<'info> {
+    fn validate(&self) -> Result<()> {
+        assert_keys_eq!(*self.vault.owner, crate::ID, MyError);
+        Ok(())
+    }
+}
+
+impl<'info> ValidateArgs<'info, WithdrawArgs> for Withdraw<'info> {
+    fn validate_args(&self, args: &WithdrawArgs) -> Result<()> {
+        invariant!(args.amount <= self.vault.lamports(), MyOtherError);
+        Ok(())
+    }
+}
+
+fn withdraw(ctx: Context<Withdraw>, args: WithdrawArgs) -> Result<()> {
+    validate_with_args!(ctx, &args);
+    Ok(())
+}
+
+fn run_withdraw(owner: &Pubkey, vault_lamports: u64, amount: u64) -> Result<()> {
+    let mut lamports = vault_lamports;
+    let vault: AccountInfo = AccountInfo::new(
+        &crate::ID,
+        false,
+        false,
+        &mut lamports,
+        &mut [],
+        owner,
+        false,
+        0,
+    );
+    let accounts = &mut Withdraw { vault };
+    let ctx = Context {
+        program_id: &crate::ID,
+        accounts,
+        remaining_accounts: &[],
+        bumps: BTreeMap::new(),
+    };
+    withdraw(ctx, WithdrawArgs { amount })
+}
+
+#[test]
+fn test_validate_with_args_pass() {
+    assert!(run_withdraw(&crate::ID, 100, 100).is_ok());
+}
+
+#[test]
+fn test_validate_with_args_arg_check_fails() {
+    assert_eq!(
+        run_withdraw(&crate::ID, 100, 101).into_cmp_error(),
+        error!(ErrorCode::MyOtherError).into_cmp_error()
+    );
+}
+
+#[test]
+fn test_validate_with_args_validate_runs_first() {
+    let other_program = Pubkey::new_unique();
+    assert_eq!(
+        run_withdraw(&other_program, 100, 100).into_cmp_error(),
+        error!(ErrorCode::MyError).into_cmp_error()
+    );
+    // both checks fail: the structural check is reported
+    assert_eq!(
+        run_withdraw(&other_program, 100, 101).into_cmp_error(),
+        error!(ErrorCode::MyError).into_cmp_error()
+    );
+}
+
+#[test]
+fn test_assert_not_unspendable_default_entries() {
+    for (name, key) in vipers::well_known::UNSPENDABLE_KEYS {
+        assert_eq!(vipers::well_known::find_unspendable(key), Some(*name));
+        assert_throws!(
+            {
+                assert_not_unspendable!(**key);
+            },
+            VipersError::UnspendableDestination
+        );
+    }
+    assert_throws!(
+        {
+            assert_not_unspendable!(Pubkey::default());
+        },
+        VipersError::UnspendableDestination
+    );
+}
+
+#[test]
+fn test_assert_not_unspendable_extra() {
+    let old_treasury = Pubkey::new_unique();
+    let wallet = Pubkey::new_unique();
+    assert_does_not_throw!({
+        assert_not_unspendable!(wallet, extra: [old_treasury]);
+    });
+    assert_throws!(
+        {
+            assert_not_unspendable!(old_treasury, extra: [Pubkey::new_unique(), old_treasury]);
+        },
+        VipersError::UnspendableDestination
+    );
+}
+
+#[test]
+fn test_assert_not_unspendable_wallet() {
+    let wallet = Pubkey::new_unique();
+    assert_eq!(vipers::well_known::find_unspendable(&wallet), None);
+    assert_does_not_throw!({
+        assert_not_unspendable!(wallet);
+    });
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_assert_owner_program_ids() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = [0; 0];
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &token::ID,
+        false,
+        0,
+    );
+    assert_does_not_throw!({
+        assert_owner!(info, token);
+    });
+    assert_does_not_throw!({
+        assert_owner!(info, token, "token account");
+    });
+    assert_throws!(
+        {
+            assert_owner!(info, system);
+        },
+        VipersError::OwnerMismatch
+    );
+    assert_throws!(
+        {
+            assert_owner!(info, token_2022, "token account");
+        },
+        VipersError::OwnerMismatch
+    );
+    let token_program_id = token::ID;
+    assert_does_not_throw!({
+        assert_owner!(info, (token_program_id), "token account");
+    });
+
+    // other identifiers are keys, not program_ids modules
+    assert_does_not_throw!({
+        assert_owner!(info, token_program_id);
+        assert_owner!(info, token_program_id, "token account");
+    });
+    let system_program_id = anchor_lang::solana_program::system_program::ID;
+    assert_throws!(
+        {
+            assert_owner!(info, system_program_id);
+        },
+        VipersError::OwnerMismatch
+    );
+    assert_throws!(
+        {
+            assert_owner!(info, system_program_id, "token account");
+        },
+        VipersError::OwnerMismatch
+    );
+}
+
+#[test]
+#[allow(clippy::eq_op)]
+fn test_invariants_all_pass() {
+    assert_does_not_throw!({
+        invariants! {
+            1 == 1,
+            2 == 2 => MyError,
+            3 == 3 => ErrorCode::MyOtherError,
+            4 == 4 => MyError, "four",
+            5 == 5, "five",
+        }
+    });
+    assert_does_not_throw!({
+        invariants! {}
+    });
+}
+
+#[test]
+#[allow(clippy::eq_op)]
+fn test_invariants_first_failure() {
+    assert_throws!(
+        {
+            invariants! {
+                1 == 1 => MyError,
+                1 == 2 => MyOtherError,
+                1 == 3 => MyError,
+            }
+        },
+        ErrorCode::MyOtherError
+    );
+    assert_throws!(
+        {
+            invariants! {
+                1 == 2 => ErrorCode::MyError, "first",
+                1 == 3 => ErrorCode::MyOtherError, "second",
+            }
+        },
+        ErrorCode::MyError
+    );
+    assert_throws!(
+        {
+            invariants! {
+                1 == 1,
+                1 == 2, "default error",
+                1 == 3 => MyError,
+            }
+        },
+        VipersError::InvariantFailed
+    );
+    assert_throws!(
+        {
+            invariants! {
+                1 == 2
+            }
+        },
+        VipersError::InvariantFailed
+    );
+}
+
+#[event]
+struct UtilizationChecked {
+    utilization: u64,
+}
+
+/// Removes the code location logs, which differ between call sites.
+fn without_code_location(logs: Vec<String>) -> Vec<String> {
+    logs.into_iter()
+        .filter(|log| !log.starts_with("Program log: Error thrown at"))
+        .collect()
+}
+
+#[test]
+fn test_invariant_and_emit_failure_matches_invariant() {
+    let utilization: u64 = 101;
+    let (expected, expected_logs) = vipers::testing::capture_logs(|| {
+        test_assertion!({
+            invariant!(utilization <= 100, MyError);
+        })
+    });
+    let (actual, actual_logs) = vipers::testing::capture_logs(|| {
+        test_assertion!({
+            invariant_and_emit!(
+                utilization <= 100,
+                MyError,
+                UtilizationChecked { utilization }
+            );
+        })
+    });
+    let actual = actual.into_cmp_error();
+    assert_eq!(actual, expected.into_cmp_error());
+    assert_eq!(actual, error!(ErrorCode::MyError).into_cmp_error());
+    assert!(actual_logs
+        .iter()
+        .any(|log| log.starts_with("Program log: Error thrown at")));
+    assert!(!actual_logs
+        .iter()
+        .any(|log| log.starts_with("Program data:")));
+    assert_eq!(
+        without_code_location(actual_logs),
+        without_code_location(expected_logs)
+    );
+}
+
+#[test]
+fn test_invariant_and_emit_success_emits_event() {
+    let utilization: u64 = 42;
+    let (result, logs) = vipers::testing::capture_logs(|| {
+        test_assertion!({
+            invariant_and_emit!(
+                utilization <= 100,
+                ErrorCode::MyError,
+                UtilizationChecked { utilization }
+            );
+        })
+    });
+    assert!(result.is_ok());
+    let expected = format!(
+        "Program data: {}",
+        anchor_lang::__private::base64::encode(anchor_lang::Event::data(&UtilizationChecked {
+            utilization
+        }))
+    );
+    assert_eq!(logs, vec![expected]);
+}
+
+/// Asserts that the logs point at the given line of this file.
+fn assert_logged_location(logs: &[String], line: u32) {
+    let expected = format!("Program log: Error thrown at {}:{}", file!(), line);
+    assert!(
+        logs.contains(&expected),
+        "{:?} does not contain {}",
+        logs,
+        expected
+    );
+}
+
+#[test]
+fn test_track_caller_small_key_map() {
+    let map: vipers::collections::SmallKeyMap<u64, 1> = Default::default();
+    let key = Pubkey::new_unique();
+    let ((result, line), logs) =
+        vipers::testing::capture_logs(|| (map.get_or_err(&key).map(|_| ()), line!()));
+    assert!(result.is_err());
+    assert_logged_location(&logs, line);
+}
+
+#[test]
+fn test_track_caller_spl_checks() {
+    use vipers::{spl::check_transfer, testing::capture_logs};
+    let src = spl_token::state::Account::default();
+    let (id, owner) = (token::ID, Pubkey::default());
+    let ((res, line), logs) = capture_logs(|| (check_transfer(&id, &src, &owner, 1), line!()));
+    assert_eq!(
+        res.into_cmp_error(),
+        error!(VipersError::InsufficientBalance).into_cmp_error()
+    );
+    assert_logged_location(&logs, line);
+}
+
+#[track_caller]
+fn add_fee(amount: u64, fee: u64) -> Result<u64> {
+    add_fee_inner(amount, fee, std::panic::Location::caller())
+}
+
+fn add_fee_inner(
+    amount: u64,
+    fee: u64,
+    location: &'static std::panic::Location<'static>,
+) -> Result<u64> {
+    Ok(unwrap_int_at!(amount.checked_add(fee), location))
+}
+
+#[test]
+fn test_unwrap_int_at() {
+    assert_eq!(add_fee(1, 2).unwrap(), 3);
+    let ((result, line), logs) = vipers::testing::capture_logs(|| (add_fee(u64::MAX, 1), line!()));
+    assert_eq!(
+        result.into_cmp_error(),
+        error!(VipersError::IntegerOverflow).into_cmp_error()
+    );
+    assert_logged_location(&logs, line);
+}
+
+/// Runs [assert_is_zero_token_account] on the account, returning the error and logs.
+fn zero_token_account_logs(
+    token_account: &spl_token::state::Account,
+    allowed_delegate: Option<Pubkey>,
+) -> (Option<CmpError>, Vec<String>) {
+    let (result, logs) = vipers::testing::capture_logs(|| {
+        test_assertion!({
+            match allowed_delegate {
+                Some(delegate) => {
+                    assert_is_zero_token_account!(token_account, allow_delegate = delegate)
+                }
+                None => assert_is_zero_token_account!(token_account),
+            }
+        })
+    });
+    (result.into_cmp_error(), logs)
+}
+
+#[test]
+fn test_assert_is_zero_token_account_amount() {
+    let token_account = spl_token::state::Account {
+        amount: 10,
+        ..Default::default()
+    };
+    let (err, logs) = zero_token_account_logs(&token_account, None);
+    assert_eq!(
+        err,
+        error!(VipersError::TokenAccountIsNonZero).into_cmp_error()
+    );
+    assert!(logs.contains(&"Program log: Amount is non-zero: 10".to_string()));
+}
+
+#[test]
+fn test_assert_is_zero_token_account_delegate() {
+    let delegate = Pubkey::new_unique();
+    let token_account = spl_token::state::Account {
+        delegate: delegate.into(),
+        ..Default::default()
+    };
+    let (err, logs) = zero_token_account_logs(&token_account, None);
+    assert_eq!(
+        err,
+        error!(VipersError::TokenAccountIsNonZero).into_cmp_error()
+    );
+    assert!(logs.contains(&format!("Program log: Delegate is set: {}", delegate)));
+}
+
+#[test]
+fn test_assert_is_zero_token_account_close_authority() {
+    let close_authority = Pubkey::new_unique();
+    let token_account = spl_token::state::Account {
+        close_authority: close_authority.into(),
+        ..Default::default()
+    };
+    let (err, logs) = zero_token_account_logs(&token_account, None);
+    assert_eq!(
+        err,
+        error!(VipersError::TokenAccountIsNonZero).into_cmp_error()
+    );
+    assert!(logs.contains(&format!(
+        "Program log: Close authority is set: {}",
+        close_authority
+    )));
+}
+
+#[test]
+fn test_assert_is_zero_token_account_allow_delegate() {
+    let delegate = Pubkey::new_unique();
+    let mut token_account = spl_token::state::Account::default();
+    assert_eq!(
+        zero_token_account_logs(&token_account, Some(delegate)).0,
+        None
+    );
+
+    token_account.delegate = delegate.into();
+    assert_eq!(
+        zero_token_account_logs(&token_account, Some(delegate)).0,
+        None
+    );
+
+    let other = Pubkey::new_unique();
+    let (err, logs) = zero_token_account_logs(&token_account, Some(other));
+    assert_eq!(
+        err,
+        error!(VipersError::TokenAccountIsNonZero).into_cmp_error()
+    );
+    assert!(logs.contains(&format!(
+        "Program log: Delegate is not allowed: expected {}, got {}",
+        other, delegate
+    )));
+
+    // the other conditions still apply
+    token_account.amount = 1;
+    let (err, logs) = zero_token_account_logs(&token_account, Some(delegate));
+    assert_eq!(
+        err,
+        error!(VipersError::TokenAccountIsNonZero).into_cmp_error()
+    );
+    assert!(logs.contains(&"Program log: Amount is non-zero: 1".to_string()));
+
+    token_account.amount = 0;
+    token_account.close_authority = other.into();
+    let (err, logs) = zero_token_account_logs(&token_account, Some(delegate));
+    assert_eq!(
+        err,
+        error!(VipersError::TokenAccountIsNonZero).into_cmp_error()
+    );
+    assert!(logs.contains(&format!("Program log: Close authority is set: {}", other)));
+}
+
+#[test]
+fn test_optional_boxed_account() {
+    let key = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let lamports = &mut 0;
+    let mut data = [0; 165];
+    spl_token::state::Account {
+        mint,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        lamports,
+        &mut data,
+        &token::ID,
+        false,
+        0,
+    );
+
+    let referrer: Option<Box<Account<anchor_spl::token::TokenAccount>>> =
+        Some(Box::new(Account::try_from_unchecked(&info).unwrap()));
+    assert_does_not_throw!({
+        let account = unwrap_optional_account!(referrer, MyError);
+        assert_keys_eq!(account, key);
+    });
+    assert_does_not_throw!({
+        with_optional_account!(referrer, |acc| {
+            assert_keys_eq!(acc.mint, mint);
+        });
+    });
+    assert_throws!(
+        {
+            with_optional_account!(referrer, |acc| {
+                assert_keys_eq!(acc.mint, key, MyError);
+            });
+        },
+        ErrorCode::MyError
+    );
+
+    let referrer: Option<Box<Account<anchor_spl::token::TokenAccount>>> = None;
+    assert_throws!(
+        {
+            unwrap_optional_account!(referrer, MyError);
+        },
+        ErrorCode::MyError
+    );
+    assert_does_not_throw!({
+        with_optional_account!(referrer, |acc| {
+            assert_keys_eq!(acc.mint, key, MyError);
+        });
+    });
+}
+
+#[test]
+fn test_optional_unchecked_account() {
+    let key = Pubkey::new_unique();
+    let lamports = &mut 0;
+    let mut data = [0; 0];
+    let info = AccountInfo::new(&key, false, false, lamports, &mut data, &key, false, 0);
+
+    let referrer = Some(UncheckedAccount::try_from(info.clone()));
+    assert_does_not_throw!({
+        let account = unwrap_optional_account!(referrer);
+        assert_keys_eq!(account, key);
+    });
+    assert_throws!(
+        {
+            with_optional_account!(referrer, |acc| assert_keys_neq!(acc, key));
+        },
+        VipersError::KeysMustNotMatch
+    );
+
+    let referrer: Option<UncheckedAccount> = None;
+    let ((), logs) = vipers::testing::capture_logs(|| {
+        assert_throws!(
+            {
+                unwrap_optional_account!(referrer);
+            },
+            VipersError::OptionUnwrapFailed
+        );
+    });
+    assert!(logs.contains(&"Program log: Missing optional account: referrer".to_string()));
+    assert_does_not_throw!({
+        with_optional_account!(referrer, |acc| assert_keys_neq!(acc, key));
+    });
+}
+
+#[test]
+fn test_assert_mint_authority() {
+    let key = Pubkey::new_unique();
+    let pda = Pubkey::new_unique();
+    let lamports = &mut 0;
+    let mut data = [0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: pda.into(),
+        is_initialized: true,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        lamports,
+        &mut data,
+        &token::ID,
+        false,
+        0,
+    );
+    let mint: Account<anchor_spl::token::Mint> = Account::try_from(&info).unwrap();
+
+    assert_does_not_throw!({
+        assert_mint_authority!(mint, pda);
+    });
+    assert_throws!(
+        {
+            assert_mint_authority!(mint, key);
+        },
+        VipersError::KeyMismatch
+    );
+    assert_throws!(
+        {
+            assert_mint_authority!(mint, key, MyError);
+        },
+        ErrorCode::MyError
+    );
+    assert_throws!(
+        {
+            assert_freeze_authority!(mint, pda, MyError);
+        },
+        VipersError::MintHasNoAuthority
+    );
+}
+
+#[test]
+fn test_assert_mint_authority_raw_mint() {
+    let pda = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let mut mint = spl_token::state::Mint::default();
+    assert_throws!(
+        {
+            assert_mint_authority!(mint, pda);
+        },
+        VipersError::MintHasNoAuthority
+    );
+
+    mint.mint_authority = other.into();
+    mint.freeze_authority = pda.into();
+    let ((), logs) = vipers::testing::capture_logs(|| {
+        assert_throws!(
+            {
+                assert_mint_authority!(mint, pda, ErrorCode::MyError);
+            },
+            ErrorCode::MyError
+        );
+    });
+    assert!(logs.contains(&"Program log: Mint authority mismatch: mint.mint_authority".to_string()));
+    assert_does_not_throw!({
+        assert_freeze_authority!(mint, pda);
+    });
+    assert_throws!(
+        {
+            assert_freeze_authority!(mint, other);
+        },
+        VipersError::KeyMismatch
+    );
+}
+
+struct RolloutAccounts {
+    config: Option<Pubkey>,
+}
+
+fn rollout(accounts: &RolloutAccounts, ran: &mut bool) -> Result<()> {
+    let config = skip_if_none!(accounts.config);
+    assert_keys_neq!(config, Pubkey::default());
+    *ran = true;
+    Ok(())
+}
+
+fn rollout_program_result(
+    accounts: &RolloutAccounts,
+    ran: &mut bool,
+) -> anchor_lang::solana_program::entrypoint::ProgramResult {
+    skip_if_none!(accounts.config);
+    *ran = true;
+    Ok(())
+}
+
+#[test]
+fn test_skip_if_none_absent() {
+    let accounts = RolloutAccounts { config: None };
+    let mut ran = false;
+    let (result, logs) = vipers::testing::capture_logs(|| rollout(&accounts, &mut ran));
+    assert!(result.is_ok());
+    assert!(!ran);
+    assert_eq!(
+        logs,
+        vec!["Program log: Skipping instruction: accounts.config was not provided"]
+    );
+
+    let (result, _) = vipers::testing::capture_logs(|| rollout_program_result(&accounts, &mut ran));
+    assert_eq!(result, Ok(()));
+    assert!(!ran);
+}
+
+#[test]
+fn test_skip_if_none_present() {
+    let accounts = RolloutAccounts {
+        config: Some(Pubkey::new_unique()),
+    };
+    let mut ran = false;
+    assert!(rollout(&accounts, &mut ran).is_ok());
+    assert!(ran);
+
+    let mut ran = false;
+    assert_eq!(rollout_program_result(&accounts, &mut ran), Ok(()));
+    assert!(ran);
+
+    // errors after the unwrap are still thrown
+    let accounts = RolloutAccounts {
+        config: Some(Pubkey::default()),
+    };
+    assert_eq!(
+        rollout(&accounts, &mut ran).into_cmp_error(),
+        error!(VipersError::KeysMustNotMatch).into_cmp_error()
+    );
+}
+
+/// Returns a bump below the canonical bump which either does or does not derive a valid address.
+fn find_non_canonical_bump(seeds: &[&[u8]], canonical_bump: u8, valid: bool) -> u8 {
+    (0..canonical_bump)
+        .rev()
+        .find(|bump| {
+            Pubkey::create_program_address(&[seeds, &[&[*bump]]].concat(), &crate::ID).is_ok()
+                == valid
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_assert_bump_eq() {
+    let user = Pubkey::new_unique();
+    let seeds: &[&[u8]] = &[b"vault", user.as_ref()];
+    let (_, bump) = Pubkey::find_program_address(seeds, &crate::ID);
+    let non_canonical = find_non_canonical_bump(seeds, bump, true);
+    let invalid = find_non_canonical_bump(seeds, bump, false);
+
+    assert_does_not_throw!({
+        assert_bump_eq!(bump, crate::ID, seeds = [b"vault", user.as_ref()]);
+    });
+    assert_throws!(
+        {
+            assert_bump_eq!(non_canonical, crate::ID, seeds = [b"vault", user.as_ref()]);
+        },
+        VipersError::UnknownBump
+    );
+    assert_throws!(
+        {
+            assert_bump_eq!(
+                invalid,
+                crate::ID,
+                seeds = [b"vault", user.as_ref()],
+                MyError
+            );
+        },
+        ErrorCode::MyError
+    );
+}
+
+#[test]
+fn test_assert_bump_eq_address() {
+    let user = Pubkey::new_unique();
+    let seeds: &[&[u8]] = &[b"vault", user.as_ref()];
+    let (vault, bump) = Pubkey::find_program_address(seeds, &crate::ID);
+    let non_canonical = find_non_canonical_bump(seeds, bump, true);
+    let invalid = find_non_canonical_bump(seeds, bump, false);
+
+    assert_does_not_throw!({
+        assert_bump_eq!(
+            bump,
+            crate::ID,
+            seeds = [b"vault", user.as_ref()],
+            address = vault
+        );
+    });
+
+    // a non-canonical bump derives a valid, but different, shadow address
+    let shadow = Pubkey::create_program_address(
+        &[b"vault".as_ref(), user.as_ref(), &[non_canonical]],
+        &crate::ID,
+    )
+    .unwrap();
+    let ((), logs) = vipers::testing::capture_logs(|| {
+        assert_throws!(
+            {
+                assert_bump_eq!(
+                    non_canonical,
+                    crate::ID,
+                    seeds = [b"vault", user.as_ref()],
+                    address = vault
+                );
+            },
+            VipersError::UnknownBump
+        );
+    });
+    assert!(logs.contains(&format!("Program log: Derived: {}", shadow)));
+    assert!(logs.contains(&format!("Program log: Expected: {}", vault)));
+
+    assert_throws!(
+        {
+            assert_bump_eq!(
+                invalid,
+                crate::ID,
+                seeds = [b"vault", user.as_ref()],
+                address = vault,
+                ErrorCode::MyError
+            );
+        },
+        ErrorCode::MyError
+    );
+}
+
+/// Overwrites the amount of a token account, as a token CPI would.
+fn set_token_amount(info: &AccountInfo, amount: u64) {
+    let mut data = info.try_borrow_mut_data().unwrap();
+    let mut token_account = spl_token::state::Account::unpack(&data).unwrap();
+    token_account.amount = amount;
+    token_account.pack_into_slice(&mut data);
+}
+
+/// Runs `f` with a token account holding `amount`, along with an `Account` and a boxed
+/// `Account` deserialized from it.
+fn with_token_account(
+    amount: u64,
+    f: impl for<'info> FnOnce(
+        &AccountInfo<'info>,
+        Account<'info, token::TokenAccount>,
+        Box<Account<'info, token::TokenAccount>>,
+    ),
+) {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        state: spl_token::state::AccountState::Initialized,
+        amount,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    vipers::testing::with_account(&token::ID, data, |info| {
+        f(
+            info,
+            Account::try_from(info).unwrap(),
+            Box::new(Account::try_from(info).unwrap()),
+        )
+    });
+}
+
+#[test]
+fn test_reload_and_get() {
+    with_token_account(100, |info, mut vault, mut boxed_vault| {
+        set_token_amount(info, 250);
+        assert_eq!(vault.amount, 100);
+        assert_does_not_throw!({
+            assert_eq!(reload_and_get!(vault).amount, 250);
+            assert_eq!(reload_and_get!(boxed_vault).amount, 250);
+        });
+        assert_eq!(vault.amount, 250);
+
+        info.try_borrow_mut_data().unwrap().fill(0);
+        let (result, logs) = vipers::testing::capture_logs(|| -> Result<()> {
+            let _ = reload_and_get!(vault).amount;
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert_eq!(logs[0], "Program log: Failed to reload account: vault");
+    });
+}
+
+#[test]
+fn test_assert_balance_change() {
+    with_token_account(100, |info, mut vault, mut boxed_vault| {
+        let previous_amount = vault.amount;
+        set_token_amount(info, 250);
+        assert_does_not_throw!({
+            assert_balance_change!(vault, previous_amount, 150_u64);
+            assert_balance_change!(boxed_vault, previous_amount, 150);
+        });
+
+        set_token_amount(info, 50);
+        assert_does_not_throw!({
+            assert_balance_change!(boxed_vault, 250, -200);
+        });
+        let (result, logs) = vipers::testing::capture_logs(|| -> Result<()> {
+            assert_balance_change!(vault, 250, -150_i64);
+            Ok(())
+        });
+        assert_eq!(
+            result.into_cmp_error(),
+            error!(VipersError::UnexpectedBalanceChange).into_cmp_error()
+        );
+        assert_eq!(
+            logs[..3],
+            [
+                "Program log: Unexpected balance change: expected -150, got -200",
+                "Program log: Old balance: 250",
+                "Program log: New balance: 50",
+            ]
+        );
+        assert!(logs[3].starts_with("Program log: Error thrown at"));
+        assert_eq!(logs[4], "Program log: Balance change of vault");
+    });
+}
+
+#[test]
+fn test_derive_ata() {
+    let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (ata, bump) = vipers::spl::derive_ata(&owner, &mint);
+    assert_eq!(ata, get_associated_token_address(&owner, &mint));
+    assert_eq!(
+        Pubkey::create_program_address(
+            &[owner.as_ref(), token::ID.as_ref(), mint.as_ref(), &[bump]],
+            &anchor_spl::associated_token::ID,
+        ),
+        Ok(ata)
+    );
+    assert_does_not_throw!({
+        assert_cached_ata!(ata, Pubkey::default(), owner, mint);
+    });
+}
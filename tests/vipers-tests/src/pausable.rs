@@ -0,0 +1,98 @@
+//! Example of declaring pause coverage with [pausable_handlers].
+
+use anchor_lang::prelude::*;
+use std::collections::BTreeMap;
+use vipers::pause::PauseFlags;
+use vipers::*;
+
+pub const DEPOSIT: u64 = 1 << 0;
+pub const WITHDRAW: u64 = 1 << 1;
+
+#[account]
+#[derive(Default)]
+pub struct Config {
+    pub pause_flags: PauseFlags,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    pub config: Account<'info, Config>,
+}
+
+mod handlers {
+    use super::*;
+
+    pub fn deposit(_ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        invariant!(amount > 0);
+        Ok(())
+    }
+
+    pub fn withdraw(_ctx: Context<Withdraw>) -> Result<()> {
+        Ok(())
+    }
+}
+
+pausable_handlers! {
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> => handlers::deposit, DEPOSIT;
+    pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> => handlers::withdraw, WITHDRAW;
+}
+
+/// Runs `f` with a config account holding the given [PauseFlags].
+fn with_config(
+    pause_flags: PauseFlags,
+    f: impl FnOnce(Account<Config>) -> Result<()>,
+) -> Result<()> {
+    let mut data = vec![];
+    Config { pause_flags }.try_serialize(&mut data)?;
+    vipers::testing::with_account(&crate::ID, data, |info| f(Account::try_from(info)?))
+}
+
+fn run_deposit(pause_flags: PauseFlags, amount: u64) -> Result<()> {
+    with_config(pause_flags, |config| {
+        deposit(
+            Context::new(&crate::ID, &mut Deposit { config }, &[], BTreeMap::new()),
+            amount,
+        )
+    })
+}
+
+fn run_withdraw(pause_flags: PauseFlags) -> Result<()> {
+    with_config(pause_flags, |config| {
+        withdraw(Context::new(
+            &crate::ID,
+            &mut Withdraw { config },
+            &[],
+            BTreeMap::new(),
+        ))
+    })
+}
+
+#[test]
+fn test_unpaused() {
+    assert!(run_deposit(PauseFlags::NONE, 1).is_ok());
+    assert!(run_withdraw(PauseFlags::NONE).is_ok());
+    // errors from the handler are propagated
+    assert_eq!(
+        run_deposit(PauseFlags::NONE, 0).into_cmp_error(),
+        error!(VipersError::InvariantFailed).into_cmp_error()
+    );
+}
+
+#[test]
+fn test_paused() {
+    let flags = PauseFlags::from_bits(DEPOSIT);
+    assert_eq!(
+        run_deposit(flags, 1).into_cmp_error(),
+        error!(VipersError::ProtocolPaused).into_cmp_error()
+    );
+    assert!(run_withdraw(flags).is_ok());
+    assert_eq!(
+        run_withdraw(PauseFlags::ALL).into_cmp_error(),
+        error!(VipersError::ProtocolPaused).into_cmp_error()
+    );
+}
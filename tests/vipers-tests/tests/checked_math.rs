@@ -0,0 +1,138 @@
+//! Property tests for [CheckedMathExt].
+
+use anchor_lang::prelude::*;
+use proptest::prelude::*;
+use vipers::{unwrap_checked, unwrap_int, CheckedMathExt, IntoCmpError, VipersError};
+
+/// Asserts that a `vsafe_*` result matches the corresponding `checked_*` result.
+fn assert_matches<T: PartialEq + std::fmt::Debug>(actual: Result<T>, expected: Option<T>) {
+    match expected {
+        Some(expected) => assert_eq!(actual.unwrap(), expected),
+        None => assert_eq!(
+            actual.into_cmp_error(),
+            error!(VipersError::IntegerOverflow).into_cmp_error()
+        ),
+    }
+}
+
+macro_rules! check_all_ops {
+    ($a: expr, $b: expr) => {{
+        let (a, b) = ($a, $b);
+        assert_matches(a.vsafe_add(b), a.checked_add(b));
+        assert_matches(a.vsafe_sub(b), a.checked_sub(b));
+        assert_matches(a.vsafe_mul(b), a.checked_mul(b));
+        assert_matches(a.vsafe_div(b), a.checked_div(b));
+    }};
+}
+
+fn mul_via_macro(a: u64, b: u64) -> Result<u64> {
+    Ok(unwrap_int!(a.checked_mul(b)))
+}
+
+fn add_sub_via_macro(a: u64, b: u64) -> Result<u64> {
+    Ok(unwrap_checked!({ a.checked_add(b)?.checked_sub(1) }))
+}
+
+/// Asserts that [unwrap_int] and [unwrap_checked] agree with [CheckedMathExt].
+fn check_macros(a: u64, b: u64) {
+    assert_eq!(
+        mul_via_macro(a, b).into_cmp_error(),
+        a.vsafe_mul(b).into_cmp_error()
+    );
+    assert_eq!(
+        add_sub_via_macro(a, b).into_cmp_error(),
+        a.vsafe_add(b)
+            .and_then(|sum| sum.vsafe_sub(1))
+            .into_cmp_error()
+    );
+}
+
+/// Values near the boundaries of `u64`, where overflow is most likely.
+fn u64_strategy() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        any::<u64>(),
+        Just(0),
+        Just(1),
+        Just(u64::MAX),
+        Just(u64::MAX - 1),
+        (u64::MAX / 2 - 2)..=(u64::MAX / 2 + 2),
+    ]
+}
+
+/// Values near the boundaries of `u128`, where overflow is most likely.
+fn u128_strategy() -> impl Strategy<Value = u128> {
+    prop_oneof![
+        any::<u128>(),
+        Just(0),
+        Just(1),
+        Just(u128::MAX),
+        any::<u64>().prop_map(u128::from),
+    ]
+}
+
+/// Values near the boundaries of `i64`, where overflow is most likely.
+fn i64_strategy() -> impl Strategy<Value = i64> {
+    prop_oneof![
+        any::<i64>(),
+        Just(0),
+        Just(1),
+        Just(-1),
+        Just(i64::MIN),
+        Just(i64::MAX),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn test_u64_matches_std(a in u64_strategy(), b in u64_strategy()) {
+        check_all_ops!(a, b);
+    }
+
+    #[test]
+    fn test_u128_matches_std(a in u128_strategy(), b in u128_strategy()) {
+        check_all_ops!(a, b);
+    }
+
+    #[test]
+    fn test_i64_matches_std(a in i64_strategy(), b in i64_strategy()) {
+        check_all_ops!(a, b);
+    }
+
+    #[test]
+    fn test_macros_match_ext(a in u64_strategy(), b in u64_strategy()) {
+        check_macros(a, b);
+    }
+}
+
+#[test]
+fn test_boundaries() {
+    let u64_values = [0, 1, 2, u64::MAX / 2, u64::MAX - 1, u64::MAX];
+    for a in u64_values {
+        for b in u64_values {
+            check_all_ops!(a, b);
+            check_macros(a, b);
+        }
+    }
+    let u128_values = [0, 1, 2, u128::MAX / 2, u128::MAX - 1, u128::MAX];
+    for a in u128_values {
+        for b in u128_values {
+            check_all_ops!(a, b);
+        }
+    }
+    let i64_values = [
+        i64::MIN,
+        i64::MIN + 1,
+        -2,
+        -1,
+        0,
+        1,
+        2,
+        i64::MAX - 1,
+        i64::MAX,
+    ];
+    for a in i64_values {
+        for b in i64_values {
+            check_all_ops!(a, b);
+        }
+    }
+}
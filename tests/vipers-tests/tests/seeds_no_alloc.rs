@@ -0,0 +1,59 @@
+//! Ensures that seeds are constructed without heap allocations.
+//!
+//! This lives in its own test binary since it installs a global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anchor_lang::prelude::*;
+use vipers::{seeds, signer_seeds};
+
+/// Allocator which counts the number of allocations made.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Runs `f`, asserting that it does not allocate.
+fn assert_no_alloc<T>(f: impl FnOnce() -> T) -> T {
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    let result = f();
+    let after = ALLOCATIONS.load(Ordering::SeqCst);
+    assert_eq!(before, after, "allocated {} times", after - before);
+    result
+}
+
+#[test]
+fn test_seeds_do_not_allocate() {
+    let user = Pubkey::new_unique();
+    let bump: u8 = 254;
+
+    let derived = assert_no_alloc(|| {
+        let seeds: [&[u8]; 3] = seeds!(b"vault", user.as_ref(), [bump]);
+        Pubkey::create_program_address(&seeds, &vipers::ID).ok()
+    });
+    let expected =
+        Pubkey::create_program_address(&[b"vault", user.as_ref(), &[bump]], &vipers::ID).ok();
+    assert_eq!(derived, expected);
+
+    let len = assert_no_alloc(|| {
+        let bump_seed = [bump];
+        let signer: [&[&[u8]]; 1] = signer_seeds!(b"vault", user.as_ref(), bump_seed);
+        let signer_ref: &[&[&[u8]]] = &signer;
+        signer_ref[0].len()
+    });
+    assert_eq!(len, 3);
+}
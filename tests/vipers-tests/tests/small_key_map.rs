@@ -0,0 +1,79 @@
+//! Property tests for [SmallKeyMap].
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::*;
+use proptest::prelude::*;
+use vipers::collections::SmallKeyMap;
+
+const CAPACITY: usize = 8;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Insert(u8, u64),
+    Remove(u8),
+    Get(u8),
+}
+
+/// Maps a small index to a key, so that operations frequently hit the same keys.
+fn key(index: u8) -> Pubkey {
+    Pubkey::new_from_array([index + 1; 32])
+}
+
+/// Applies the operations to a [SmallKeyMap] and a reference [HashMap],
+/// asserting that they behave identically.
+fn check_against_hashmap(ops: &[Op]) {
+    let mut map: SmallKeyMap<u64, CAPACITY> = SmallKeyMap::default();
+    let mut reference: HashMap<Pubkey, u64> = HashMap::new();
+    for op in ops {
+        match *op {
+            Op::Insert(index, value) => {
+                let result = map.insert(key(index), value);
+                if reference.len() < CAPACITY || reference.contains_key(&key(index)) {
+                    assert!(result.is_ok());
+                    reference.insert(key(index), value);
+                } else {
+                    assert!(result.is_err());
+                }
+            }
+            Op::Remove(index) => {
+                assert_eq!(map.remove(&key(index)).ok(), reference.remove(&key(index)));
+            }
+            Op::Get(index) => {
+                assert_eq!(map.get(&key(index)), reference.get(&key(index)));
+                assert_eq!(map.get_or_err(&key(index)).ok(), reference.get(&key(index)));
+            }
+        }
+        assert_eq!(map.len(), reference.len());
+        for (k, v) in map.iter() {
+            assert_eq!(reference.get(k), Some(v));
+        }
+    }
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    let index = 0..(2 * CAPACITY as u8);
+    prop_oneof![
+        (index.clone(), any::<u64>()).prop_map(|(i, v)| Op::Insert(i, v)),
+        index.clone().prop_map(Op::Remove),
+        index.prop_map(Op::Get),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn test_matches_hashmap(ops in prop::collection::vec(op_strategy(), 0..128)) {
+        check_against_hashmap(&ops);
+    }
+}
+
+#[test]
+fn test_fill_remove_refill() {
+    let mut ops: Vec<Op> = (0..CAPACITY as u8 + 1)
+        .map(|i| Op::Insert(i, i.into()))
+        .collect();
+    ops.extend((0..CAPACITY as u8).map(Op::Remove));
+    ops.extend((0..CAPACITY as u8).map(|i| Op::Insert(i, 100 + u64::from(i))));
+    ops.extend((0..2 * CAPACITY as u8).map(Op::Get));
+    check_against_hashmap(&ops);
+}
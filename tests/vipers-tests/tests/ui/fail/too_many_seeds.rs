@@ -0,0 +1,7 @@
+use vipers::{seeds, signer_seeds};
+
+fn main() {
+    let s = b"s";
+    let _seeds = seeds!(s, s, s, s, s, s, s, s, s, s, s, s, s, s, s, s, s);
+    let _signer_seeds = signer_seeds!(s, s, s, s, s, s, s, s, s, s, s, s, s, s, s, s, s);
+}
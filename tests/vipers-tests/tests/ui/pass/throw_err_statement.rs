@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use vipers::throw_err;
+
+#[error_code]
+pub enum ErrorCode {
+    MyError,
+}
+
+fn statement_ident(fail: bool) -> Result<()> {
+    if fail {
+        throw_err!(MyError);
+    }
+    Ok(())
+}
+
+fn statement_expr(fail: bool) -> Result<()> {
+    if fail {
+        throw_err!(ErrorCode::MyError);
+    }
+    Ok(())
+}
+
+fn statement_tail() -> Result<()> {
+    throw_err!(MyError);
+}
+
+fn main() {
+    assert!(statement_ident(false).is_ok());
+    assert!(statement_expr(false).is_ok());
+    assert!(statement_tail().is_err());
+}
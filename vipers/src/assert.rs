@@ -81,10 +81,16 @@ macro_rules! program_err {
 }
 
 /// Logs where in the code the macro was invoked.
+///
+/// Within a `#[track_caller]` function, this logs the location of the caller of the function.
+/// A [core::panic::Location] may also be provided explicitly.
 #[macro_export]
 macro_rules! log_code_location {
     () => {
-        msg!("Error thrown at {}:{}", file!(), line!());
+        $crate::log_code_location!(::core::panic::Location::caller())
+    };
+    ($location: expr $(,)?) => {
+        $crate::cold::log_code_location($location)
     };
 }
 
@@ -132,11 +138,32 @@ macro_rules! unwrap_opt_block {
 /// # Ok(())
 /// # }
 /// ```
+///
+/// Like [unwrap_int], a program error may be thrown instead via `=>`:
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// #[error_code]
+/// pub enum ErrorCode { MathOverflow }
+///
+/// # fn main() {
+/// assert_throws!({
+///   unwrap_checked!({ 1_u64.checked_sub(2) } => MathOverflow);
+/// }, ErrorCode::MathOverflow);
+/// # }
+/// ```
 #[macro_export]
 macro_rules! unwrap_checked {
     ($body:block $(,)?) => {
         $crate::unwrap_opt_block!($body, $crate::VipersError::IntegerOverflow)
     };
+    ($body:block => $err_code: ident $(,)?) => {
+        $crate::unwrap_opt_block!($body, crate::ErrorCode::$err_code)
+    };
+    ($body:block => $err: expr $(,)?) => {
+        $crate::unwrap_opt_block!($body, $err)
+    };
 }
 
 /// Throws an error.
@@ -155,21 +182,41 @@ macro_rules! unwrap_checked {
 /// Ok(())
 /// # }
 /// ```
+///
+/// The macro expands to a diverging block, so it may also be used in expression position,
+/// e.g. in match arms and `if`/`else` expressions:
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[error_code]
+/// # pub enum ErrorCode { MyError }
+/// # #[macro_use] extern crate vipers; fn main() -> Result<()> {
+/// let opt: Option<u64> = Some(1);
+/// let value = match opt {
+///     Some(v) => v,
+///     None => throw_err!(MyError),
+/// };
+/// let doubled = if value < 10 { value * 2 } else { throw_err!(ErrorCode::MyError) };
+/// assert_eq!(doubled, 2);
+/// # Ok(())
+/// # }
+/// ```
 #[macro_export]
 macro_rules! throw_err {
     ($error:ident $(,)?) => {
-        $crate::throw_err!(crate::ErrorCode::$error);
+        $crate::throw_err!(crate::ErrorCode::$error)
     };
-    ($error:expr $(,)?) => {
+    ($error:expr $(,)?) => {{
         $crate::log_code_location!();
         return Err(::anchor_lang::prelude::error!($error));
-    };
+    }};
 }
 
 /// Asserts that the ATA is the one of the given owner/mint.
 ///
 /// Warning: this uses a lot of compute units due to the need to generate a PDA.
-/// It is recommended to cache this value.
+/// It is recommended to cache this value via [crate::spl::derive_ata] and check it
+/// with [assert_cached_ata].
 #[cfg(feature = "spl-associated-token-account")]
 #[macro_export]
 #[deprecated(
@@ -232,6 +279,10 @@ macro_rules! assert_is_ata {
 
 /// Asserts that an account is owned by the given program.
 ///
+/// The owner may be a key, or the name of a module in [crate::program_ids],
+/// i.e. `system`, `token`, `token_2022`, or `associated_token`,
+/// e.g. `assert_owner!(account, token)`. Any other identifier is treated as a key.
+///
 /// As of Anchor 0.15, Anchor handles this for you automatically.
 /// You should not need to use this.
 #[macro_export]
@@ -240,15 +291,38 @@ macro_rules! assert_is_ata {
     note = "As of Anchor 0.15, Anchor handles this for you automatically."
 )]
 macro_rules! assert_owner {
-    ($program_account: expr, $owner: expr $(,)?) => {
-        $crate::assert_owner!($program_account, $owner, "owner mismatch")
+    (@program $program_account: expr, $program: ident $(,)?) => {
+        $crate::assert_owner!($program_account, $crate::program_ids::$program::ID)
+    };
+    (@program $program_account: expr, $program: ident, $msg: expr $(,)?) => {
+        $crate::assert_owner!($program_account, $crate::program_ids::$program::ID, $msg)
+    };
+    ($program_account: expr, system $(,)?) => {
+        $crate::assert_owner!(@program $program_account, system)
     };
-    ($program_account: expr, $owner: ident $(,)?) => {
-        $crate::assert_owner!($program_account, $owner);
+    ($program_account: expr, system, $msg: expr $(,)?) => {
+        $crate::assert_owner!(@program $program_account, system, $msg)
     };
-    ($program_account: expr, $owner: ident, $msg: expr $(,)?) => {
-        let __program_id = $crate::program_ids::$owner::ID;
-        $crate::assert_owner!($program_account, $owner, $msg);
+    ($program_account: expr, token $(,)?) => {
+        $crate::assert_owner!(@program $program_account, token)
+    };
+    ($program_account: expr, token, $msg: expr $(,)?) => {
+        $crate::assert_owner!(@program $program_account, token, $msg)
+    };
+    ($program_account: expr, token_2022 $(,)?) => {
+        $crate::assert_owner!(@program $program_account, token_2022)
+    };
+    ($program_account: expr, token_2022, $msg: expr $(,)?) => {
+        $crate::assert_owner!(@program $program_account, token_2022, $msg)
+    };
+    ($program_account: expr, associated_token $(,)?) => {
+        $crate::assert_owner!(@program $program_account, associated_token)
+    };
+    ($program_account: expr, associated_token, $msg: expr $(,)?) => {
+        $crate::assert_owner!(@program $program_account, associated_token, $msg)
+    };
+    ($program_account: expr, $owner: expr $(,)?) => {
+        $crate::assert_owner!($program_account, $owner, "owner mismatch")
     };
     ($program_account: expr, $owner: expr, $msg: expr $(,)?) => {{
         let __program_account =
@@ -295,6 +369,21 @@ macro_rules! assert_owner {
 /// ```
 #[macro_export]
 macro_rules! assert_keys_eq {
+    (@check $account_a: expr, $account_b: expr, $err: expr, $message: expr $(,)?) => {{
+        let __key_a = &$account_a;
+        let __key_b = &$account_b;
+        let __account_a = $crate::AsKeyRef::as_key_ref(__key_a);
+        let __account_b = $crate::AsKeyRef::as_key_ref(__key_b);
+        if !$crate::keys_eq(__account_a, __account_b) {
+            $crate::cold::fail_keys(
+                $message,
+                stringify!($account_a != $account_b),
+                __account_a,
+                __account_b,
+            );
+            $crate::throw_err!($err);
+        }
+    }};
     ($account_a: expr, $account_b: expr $(,)?) => {
         $crate::assert_keys_eq!($account_a, $account_b, $crate::VipersError::KeyMismatch);
     };
@@ -303,28 +392,28 @@ macro_rules! assert_keys_eq {
     };
     ($account_a: expr, $account_b: expr, $msg: literal $(,)?) => {
         $crate::assert_keys_eq!(
-            $account_a,
+            @check $account_a,
             $account_b,
             $crate::VipersError::KeyMismatch,
-            &*format!("Key mismatch: {}", $msg),
+            $crate::cold::Message::Prefixed("Key mismatch", $msg)
         );
     };
     ($account_a: expr, $account_b: expr, $err: expr $(,)?) => {
-        $crate::assert_keys_eq!($account_a, $account_b, $err, $crate::format_err!($err));
+        $crate::assert_keys_eq!(
+            @check $account_a,
+            $account_b,
+            $err,
+            $crate::cold::Message::Error(&$err)
+        );
+    };
+    ($account_a: expr, $account_b: expr, $err: expr, $msg: expr $(,)?) => {
+        $crate::assert_keys_eq!(
+            @check $account_a,
+            $account_b,
+            $err,
+            $crate::cold::Message::Str($msg)
+        );
     };
-    ($account_a: expr, $account_b: expr, $err: expr, $msg: expr $(,)?) => {{
-        let __key_a = &$account_a;
-        let __key_b = &$account_b;
-        let __account_a = $crate::AsKeyRef::as_key_ref(__key_a);
-        let __account_b = $crate::AsKeyRef::as_key_ref(__key_b);
-        if __account_a != __account_b {
-            msg!($msg);
-            msg!(stringify!($account_a != $account_b));
-            msg!("Left: {}", __account_a);
-            msg!("Right: {}", __account_b);
-            $crate::throw_err!($err);
-        }
-    }};
 }
 
 /// Asserts that a token account is "zero".
@@ -337,6 +426,9 @@ macro_rules! assert_keys_eq {
 /// This is useful for checking to see that a bad actor cannot
 /// modify PDA-owned token accounts.
 ///
+/// A specific delegate may be permitted via `allow_delegate = expected_delegate`.
+/// Each condition is checked separately, and the failing one is logged.
+///
 /// # Example
 ///
 /// ```
@@ -370,38 +462,271 @@ macro_rules! assert_keys_eq {
 /// assert_throws!({
 ///   assert_is_zero_token_account!(non_zero_account);
 /// }, vipers::VipersError::TokenAccountIsNonZero);
+///
+/// let program_authority = Pubkey::new_unique();
+/// let mut delegated_account = spl_token::state::Account::default();
+/// delegated_account.delegate = program_authority.into();
+/// assert_does_not_throw!({
+///   assert_is_zero_token_account!(delegated_account, allow_delegate = program_authority);
+/// });
+/// assert_throws!({
+///   assert_is_zero_token_account!(delegated_account, allow_delegate = spl_token::ID);
+/// }, vipers::VipersError::TokenAccountIsNonZero);
 /// # }
 /// ```
 #[macro_export]
 macro_rules! assert_is_zero_token_account {
+    (@check $token_account: expr, $allowed_delegate: expr, $err: expr, $message: expr $(,)?) => {{
+        let __token_account = &$token_account;
+        if __token_account.amount != 0 {
+            $message.log();
+            msg!("Amount is non-zero: {}", __token_account.amount);
+            $crate::throw_err!($err);
+        }
+        let __allowed_delegate: ::core::option::Option<&::anchor_lang::prelude::Pubkey> =
+            $allowed_delegate;
+        if let ::anchor_lang::solana_program::program_option::COption::Some(ref __delegate) =
+            __token_account.delegate
+        {
+            match __allowed_delegate {
+                Some(__allowed) if $crate::keys_eq(__delegate, __allowed) => {}
+                Some(__allowed) => {
+                    $message.log();
+                    msg!("Delegate is not allowed: expected {}, got {}", __allowed, __delegate);
+                    $crate::throw_err!($err);
+                }
+                None => {
+                    $message.log();
+                    msg!("Delegate is set: {}", __delegate);
+                    $crate::throw_err!($err);
+                }
+            }
+        }
+        if let ::anchor_lang::solana_program::program_option::COption::Some(ref __close_authority) =
+            __token_account.close_authority
+        {
+            $message.log();
+            msg!("Close authority is set: {}", __close_authority);
+            $crate::throw_err!($err);
+        }
+    }};
     ($token_account: expr $(,)?) => {
         $crate::assert_is_zero_token_account!(
             $token_account,
             $crate::VipersError::TokenAccountIsNonZero
         );
     };
+    ($token_account: expr, allow_delegate = $delegate: expr $(,)?) => {
+        $crate::assert_is_zero_token_account!(
+            @check $token_account,
+            ::core::option::Option::Some($crate::AsKeyRef::as_key_ref(&$delegate)),
+            $crate::VipersError::TokenAccountIsNonZero,
+            $crate::cold::Message::Error(&$crate::VipersError::TokenAccountIsNonZero)
+        );
+    };
     ($token_account: expr, $err_code: ident $(,)?) => {
         $crate::assert_is_zero_token_account!($token_account, crate::ErrorCode::$err_code);
     };
     ($token_account: expr, $msg: literal $(,)?) => {
         $crate::assert_is_zero_token_account!(
-            $token_account,
+            @check $token_account,
+            ::core::option::Option::None,
             $crate::VipersError::TokenAccountIsNonZero,
-            &*format!("Token account is non-zero: {}", $msg),
+            $crate::cold::Message::Prefixed("Token account is non-zero", $msg)
         );
     };
     ($token_account: expr, $err: expr $(,)?) => {
-        $crate::assert_is_zero_token_account!($token_account, $err, $crate::format_err!($err));
+        $crate::assert_is_zero_token_account!(
+            @check $token_account,
+            ::core::option::Option::None,
+            $err,
+            $crate::cold::Message::Error(&$err)
+        );
     };
-    ($token_account: expr, $err: expr, $msg: expr $(,)?) => {{
-        $crate::invariant!(
-            $token_account.amount == 0
-                && $token_account.delegate.is_none()
-                && $token_account.close_authority.is_none(),
+    ($token_account: expr, $err: expr, $msg: expr $(,)?) => {
+        $crate::assert_is_zero_token_account!(
+            @check $token_account,
+            ::core::option::Option::None,
             $err,
-            $msg
+            $crate::cold::Message::Str($msg)
         );
+    };
+}
+
+/// Asserts that the mint authority of a mint is the expected key.
+///
+/// This accepts both Anchor's [anchor_spl::token::Mint] and the raw SPL Token mint.
+/// Throws [crate::VipersError::MintHasNoAuthority] if the mint has no mint authority,
+/// and [crate::VipersError::KeyMismatch] (or the provided error) if it is set to a different key.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate vipers;
+/// # use anchor_lang::prelude::*;
+/// # use anchor_spl::token::*;
+/// # fn main() {
+/// let pda = Pubkey::new_unique();
+/// let mut mint = spl_token::state::Mint::default();
+/// assert_throws!({
+///   assert_mint_authority!(mint, pda);
+/// }, vipers::VipersError::MintHasNoAuthority);
+///
+/// mint.mint_authority = pda.into();
+/// assert_does_not_throw!({
+///   assert_mint_authority!(mint, pda);
+/// });
+/// assert_throws!({
+///   assert_mint_authority!(mint, Pubkey::new_unique());
+/// }, vipers::VipersError::KeyMismatch);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_mint_authority {
+    (@check $authority: expr, $label: literal, $expected: expr, $err: expr $(,)?) => {
+        match $authority {
+            ::anchor_lang::solana_program::program_option::COption::Some(ref __authority) => {
+                $crate::assert_keys_eq!(
+                    __authority,
+                    $expected,
+                    $err,
+                    concat!($label, " mismatch: ", stringify!($authority))
+                );
+            }
+            ::anchor_lang::solana_program::program_option::COption::None => {
+                msg!(concat!($label, " is not set: ", stringify!($authority)));
+                $crate::throw_err!($crate::VipersError::MintHasNoAuthority);
+            }
+        }
+    };
+    ($mint: expr, $expected: expr $(,)?) => {
+        $crate::assert_mint_authority!($mint, $expected, $crate::VipersError::KeyMismatch)
+    };
+    ($mint: expr, $expected: expr, $err_code: ident $(,)?) => {
+        $crate::assert_mint_authority!($mint, $expected, crate::ErrorCode::$err_code)
+    };
+    ($mint: expr, $expected: expr, $err: expr $(,)?) => {
+        $crate::assert_mint_authority!(
+            @check $mint.mint_authority,
+            "Mint authority",
+            $expected,
+            $err
+        )
+    };
+}
+
+/// Asserts that the freeze authority of a mint is the expected key.
+///
+/// This accepts both Anchor's [anchor_spl::token::Mint] and the raw SPL Token mint.
+/// Throws [crate::VipersError::MintHasNoAuthority] if the mint has no freeze authority,
+/// and [crate::VipersError::KeyMismatch] (or the provided error) if it is set to a different key.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate vipers;
+/// # use anchor_lang::prelude::*;
+/// # use anchor_spl::token::*;
+/// # fn main() {
+/// let pda = Pubkey::new_unique();
+/// let mut mint = spl_token::state::Mint::default();
+/// mint.mint_authority = pda.into();
+/// assert_throws!({
+///   assert_freeze_authority!(mint, pda);
+/// }, vipers::VipersError::MintHasNoAuthority);
+///
+/// mint.freeze_authority = pda.into();
+/// assert_does_not_throw!({
+///   assert_freeze_authority!(mint, pda);
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_freeze_authority {
+    ($mint: expr, $expected: expr $(,)?) => {
+        $crate::assert_freeze_authority!($mint, $expected, $crate::VipersError::KeyMismatch)
+    };
+    ($mint: expr, $expected: expr, $err_code: ident $(,)?) => {
+        $crate::assert_freeze_authority!($mint, $expected, crate::ErrorCode::$err_code)
+    };
+    ($mint: expr, $expected: expr, $err: expr $(,)?) => {
+        $crate::assert_mint_authority!(
+            @check $mint.freeze_authority,
+            "Freeze authority",
+            $expected,
+            $err
+        )
+    };
+}
+
+/// Asserts that an account created via Anchor's `init_if_needed` holds the state that
+/// `init` would have set.
+///
+/// `init_if_needed` skips initialization if the account already exists, so a
+/// pre-existing account may have been initialized by another party with different
+/// fields. This macro should be called unconditionally once the account is available:
+/// it trivially passes for fresh accounts and catches mismatched prior state.
+///
+/// The three-argument form checks the `owner` and `mint` of a token account.
+/// Arbitrary key fields may also be listed as `field = expected_key`. Mismatched `mint`
+/// fields throw [crate::VipersError::MintMismatch]; other fields throw
+/// [crate::VipersError::KeyMismatch].
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() {
+/// let owner = Pubkey::new_unique();
+/// let mint = Pubkey::new_unique();
+/// let vault = spl_token::state::Account {
+///     owner,
+///     mint,
+///     ..Default::default()
+/// };
+/// assert_does_not_throw!({
+///     assert_init_if_needed_consistent!(vault, owner, mint);
+///     assert_init_if_needed_consistent!(vault, owner = owner);
+/// });
+/// assert_throws!({
+///     assert_init_if_needed_consistent!(vault, Pubkey::new_unique(), mint);
+/// }, vipers::VipersError::KeyMismatch);
+/// assert_throws!({
+///     assert_init_if_needed_consistent!(vault, owner, Pubkey::new_unique());
+/// }, vipers::VipersError::MintMismatch);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_init_if_needed_consistent {
+    (@error mint) => {
+        $crate::VipersError::MintMismatch
+    };
+    (@error $field: ident) => {
+        $crate::VipersError::KeyMismatch
+    };
+    ($account: expr, $($field: ident = $expected: expr),+ $(,)?) => {{
+        $(
+            $crate::assert_keys_eq!(
+                $account.$field,
+                $expected,
+                $crate::assert_init_if_needed_consistent!(@error $field),
+                concat!(
+                    "init_if_needed: ",
+                    stringify!($account),
+                    " already existed with a different ",
+                    stringify!($field),
+                    ". It may have been initialized before this instruction."
+                )
+            );
+        )+
     }};
+    ($account: expr, $expected_authority: expr, $expected_mint: expr $(,)?) => {
+        $crate::assert_init_if_needed_consistent!(
+            $account,
+            owner = $expected_authority,
+            mint = $expected_mint
+        )
+    };
 }
 
 /// Asserts that two accounts do not share the same key.
@@ -421,6 +746,21 @@ macro_rules! assert_is_zero_token_account {
 /// ```
 #[macro_export]
 macro_rules! assert_keys_neq {
+    (@check $account_a: expr, $account_b: expr, $err: expr, $message: expr $(,)?) => {{
+        let __key_a = &$account_a;
+        let __key_b = &$account_b;
+        let __account_a = $crate::AsKeyRef::as_key_ref(__key_a);
+        let __account_b = $crate::AsKeyRef::as_key_ref(__key_b);
+        if $crate::keys_eq(__account_a, __account_b) {
+            $crate::cold::fail_keys(
+                $message,
+                stringify!($account_a == $account_b),
+                __account_a,
+                __account_b,
+            );
+            $crate::throw_err!($err);
+        }
+    }};
     ($account_a: expr, $account_b: expr $(,)?) => {
         $crate::assert_keys_neq!(
             $account_a,
@@ -433,28 +773,28 @@ macro_rules! assert_keys_neq {
     };
     ($account_a: expr, $account_b: expr, $msg: literal $(,)?) => {
         $crate::assert_keys_neq!(
-            $account_a,
+            @check $account_a,
             $account_b,
             $crate::VipersError::KeysMustNotMatch,
-            &*format!("Keys must not match: {}", $msg),
+            $crate::cold::Message::Prefixed("Keys must not match", $msg)
         );
     };
     ($account_a: expr, $account_b: expr, $err: expr $(,)?) => {
-        $crate::assert_keys_neq!($account_a, $account_b, $err, $crate::format_err!($err));
+        $crate::assert_keys_neq!(
+            @check $account_a,
+            $account_b,
+            $err,
+            $crate::cold::Message::Error(&$err)
+        );
+    };
+    ($account_a: expr, $account_b: expr, $err: expr, $msg: expr $(,)?) => {
+        $crate::assert_keys_neq!(
+            @check $account_a,
+            $account_b,
+            $err,
+            $crate::cold::Message::Str($msg)
+        );
     };
-    ($account_a: expr, $account_b: expr, $err: expr, $msg: expr $(,)?) => {{
-        let __key_a = &$account_a;
-        let __key_b = &$account_b;
-        let __account_a = $crate::AsKeyRef::as_key_ref(__key_a);
-        let __account_b = $crate::AsKeyRef::as_key_ref(__key_b);
-        if __account_a == __account_b {
-            msg!($msg);
-            msg!(stringify!($account_a == $account_b));
-            msg!("Left: {}", __account_a);
-            msg!("Right: {}", __account_b);
-            $crate::throw_err!($err);
-        }
-    }};
 }
 
 /// Ensures an [Option] can be unwrapped, otherwise returns the error.
@@ -492,11 +832,75 @@ macro_rules! unwrap_or_err {
 /// Ok(())
 /// # }
 /// ```
+///
+/// The [crate::VipersError::IntegerOverflow] error may be replaced with a program error
+/// via `=>`, so that clients only see the error codes of the program:
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// #[error_code]
+/// pub enum ErrorCode { MathOverflow }
+///
+/// # fn main() {
+/// assert_throws!({
+///   unwrap_int!(1_u64.checked_sub(2) => MathOverflow);
+/// }, ErrorCode::MathOverflow);
+/// assert_throws!({
+///   unwrap_int!(1_u64.checked_sub(2) => ErrorCode::MathOverflow);
+/// }, ErrorCode::MathOverflow);
+/// # }
+/// ```
 #[macro_export]
 macro_rules! unwrap_int {
     ($option:expr $(,)?) => {
         $crate::unwrap_opt!($option, $crate::VipersError::IntegerOverflow)
     };
+    ($option:expr => $err_code: ident $(,)?) => {
+        $crate::unwrap_opt!($option, crate::ErrorCode::$err_code)
+    };
+    ($option:expr => $err: expr $(,)?) => {
+        $crate::unwrap_opt!($option, $err)
+    };
+}
+
+/// Unwraps the result of a checked integer operation, logging the given [core::panic::Location]
+/// as the location of the error.
+///
+/// This is useful for threading the location of the original call site through layered helpers.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// use core::panic::Location;
+///
+/// #[track_caller]
+/// fn add_fee(amount: u64, fee: u64) -> Result<u64> {
+///     add_fee_inner(amount, fee, Location::caller())
+/// }
+///
+/// fn add_fee_inner(amount: u64, fee: u64, location: &'static Location<'static>) -> Result<u64> {
+///     Ok(unwrap_int_at!(amount.checked_add(fee), location))
+/// }
+///
+/// # fn main() {
+/// assert!(add_fee(u64::MAX, 1).is_err()); // logs the location of this line
+/// # }
+/// ```
+#[macro_export]
+macro_rules! unwrap_int_at {
+    ($option: expr, $location: expr $(,)?) => {
+        $option.ok_or_else(|| -> anchor_lang::error::Error {
+            $crate::cold::fail_unwrap_opt(
+                &$crate::VipersError::IntegerOverflow,
+                stringify!($option),
+            );
+            $crate::log_code_location!($location);
+            anchor_lang::prelude::error!($crate::VipersError::IntegerOverflow)
+        })?
+    };
 }
 
 /// Unwraps a bump seed.
@@ -524,6 +928,139 @@ macro_rules! unwrap_bump {
     };
 }
 
+/// Asserts that a stored bump is the canonical bump of a program derived address,
+/// throwing [crate::VipersError::UnknownBump] (or the provided error) otherwise.
+///
+/// This prevents non-canonical bumps from being used to derive shadow addresses.
+/// The canonical bump is found via [Pubkey::find_program_address], which is expensive.
+///
+/// If the expected address is already known, e.g. it is the key of an account, the
+/// `address = key` variant instead re-derives the address from the stored bump via
+/// [Pubkey::create_program_address], and checks that it matches. This is much cheaper, but
+/// only checks the stored bump against the address: the address itself must be
+/// canonical, e.g. because Anchor derived it with `bump` at initialization.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// # fn main() -> Result<()> {
+/// let user = Pubkey::new_unique();
+/// let (vault, bump) =
+///     Pubkey::find_program_address(&[b"vault", user.as_ref()], &vipers::ID);
+///
+/// assert_bump_eq!(bump, vipers::ID, seeds = [b"vault", user.as_ref()]);
+/// assert_bump_eq!(bump, vipers::ID, seeds = [b"vault", user.as_ref()], address = vault);
+///
+/// assert_throws!({
+///   assert_bump_eq!(bump.wrapping_sub(1), vipers::ID, seeds = [b"vault", user.as_ref()]);
+/// }, vipers::VipersError::UnknownBump);
+/// # Ok(()) }
+/// ```
+#[macro_export]
+macro_rules! assert_bump_eq {
+    ($bump: expr, $program_id: expr, seeds = [$($seed: expr),+ $(,)?] $(,)?) => {
+        $crate::assert_bump_eq!(
+            $bump,
+            $program_id,
+            seeds = [$($seed),+],
+            $crate::VipersError::UnknownBump
+        )
+    };
+    (
+        $bump: expr,
+        $program_id: expr,
+        seeds = [$($seed: expr),+ $(,)?],
+        address = $address: expr $(,)?
+    ) => {
+        $crate::assert_bump_eq!(
+            $bump,
+            $program_id,
+            seeds = [$($seed),+],
+            address = $address,
+            $crate::VipersError::UnknownBump
+        )
+    };
+    (
+        $bump: expr,
+        $program_id: expr,
+        seeds = [$($seed: expr),+ $(,)?],
+        address = $address: expr,
+        $err_code: ident $(,)?
+    ) => {
+        $crate::assert_bump_eq!(
+            $bump,
+            $program_id,
+            seeds = [$($seed),+],
+            address = $address,
+            crate::ErrorCode::$err_code
+        )
+    };
+    (
+        $bump: expr,
+        $program_id: expr,
+        seeds = [$($seed: expr),+ $(,)?],
+        address = $address: expr,
+        $err: expr $(,)?
+    ) => {{
+        let __bump: u8 = $bump;
+        let __bump_seed = [__bump];
+        let __seeds = $crate::seeds!($($seed),+, __bump_seed);
+        let __program_id = $crate::AsKeyRef::as_key_ref(&$program_id);
+        let __expected = $crate::AsKeyRef::as_key_ref(&$address);
+        match ::anchor_lang::prelude::Pubkey::create_program_address(&__seeds, __program_id) {
+            Ok(ref __derived) if $crate::keys_eq(__derived, __expected) => {}
+            Ok(__derived) => {
+                msg!("Bump does not derive the expected address: bump {}", __bump);
+                msg!("Derived: {}", __derived);
+                msg!("Expected: {}", __expected);
+                $crate::throw_err!($err);
+            }
+            Err(_) => {
+                msg!("Invalid bump: {} does not derive a program address", __bump);
+                msg!("Expected: {}", __expected);
+                $crate::throw_err!($err);
+            }
+        }
+    }};
+    (
+        $bump: expr,
+        $program_id: expr,
+        seeds = [$($seed: expr),+ $(,)?],
+        $err_code: ident $(,)?
+    ) => {
+        $crate::assert_bump_eq!(
+            $bump,
+            $program_id,
+            seeds = [$($seed),+],
+            crate::ErrorCode::$err_code
+        )
+    };
+    (
+        $bump: expr,
+        $program_id: expr,
+        seeds = [$($seed: expr),+ $(,)?],
+        $err: expr $(,)?
+    ) => {{
+        let __bump: u8 = $bump;
+        let __seeds = $crate::seeds!($($seed),+);
+        let (__address, __canonical_bump) = ::anchor_lang::prelude::Pubkey::find_program_address(
+            &__seeds,
+            $crate::AsKeyRef::as_key_ref(&$program_id),
+        );
+        if __bump != __canonical_bump {
+            msg!(
+                "Bump mismatch: stored {}, canonical {}",
+                __bump,
+                __canonical_bump
+            );
+            msg!("Derived address: {}", __address);
+            $crate::throw_err!($err);
+        }
+    }};
+}
+
 /// Tries to unwrap the [Result], otherwise returns the error
 ///
 /// # Example
@@ -608,6 +1145,12 @@ macro_rules! try_or_err {
 /// ```
 #[macro_export]
 macro_rules! invariant {
+    (@check $invariant:expr, $err:expr, $message: expr $(,)?) => {{
+        if !($invariant) {
+            $crate::cold::fail_invariant($message, stringify!($invariant));
+            $crate::throw_err!($err);
+        }
+    }};
     ($invariant: expr $(,)?) => {
         $crate::invariant!($invariant, $crate::VipersError::InvariantFailed);
     };
@@ -619,21 +1162,401 @@ macro_rules! invariant {
     };
     ($invariant: expr, $msg: literal $(,)?) => {
         $crate::invariant!(
-            $invariant,
+            @check $invariant,
             $crate::VipersError::InvariantFailed,
-            &*format!("Invariant failed: {}", $msg)
+            $crate::cold::Message::Prefixed("Invariant failed", $msg)
         );
     };
     ($invariant:expr, $err:expr $(,)?) => {
-        $crate::invariant!($invariant, $err, $crate::format_err!($err));
+        $crate::invariant!(@check $invariant, $err, $crate::cold::Message::Error(&$err));
     };
-    ($invariant:expr, $err:expr, $msg: expr $(,)?) => {{
-        if !($invariant) {
-            msg!($msg);
-            msg!(stringify!($invariant));
-            $crate::throw_err!($err);
+    ($invariant:expr, $err:expr, $msg: expr $(,)?) => {
+        $crate::invariant!(@check $invariant, $err, $crate::cold::Message::Str($msg));
+    };
+}
+
+/// Asserts that several invariants hold, in order, throwing on the first one that fails.
+///
+/// Each entry is a condition, optionally followed by `=> error`, and optionally followed by
+/// a message string. Every entry expands to an [invariant], so logging is identical.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate vipers;
+/// # use anchor_lang::prelude::*;
+/// #[error_code]
+/// pub enum ErrorCode { MyError, MyOtherError }
+///
+/// # fn main() {
+/// let amount: u64 = 10;
+/// let max: u64 = 100;
+/// assert_does_not_throw!({
+///     invariants! {
+///         amount > 0,
+///         amount <= max => MyError,
+///         amount % 2 == 0 => ErrorCode::MyOtherError, "amount must be even",
+///         max < 1_000, "max too large",
+///     }
+/// });
+/// assert_throws!({
+///     invariants! {
+///         amount > 0 => MyError,
+///         amount > max => MyOtherError,
+///         amount > 1_000 => MyError,
+///     }
+/// }, ErrorCode::MyOtherError);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! invariants {
+    (@munch) => {};
+    (@munch $invariant: expr => $err_code: ident, $msg: literal $(, $($rest: tt)*)?) => {
+        $crate::invariant!($invariant, $err_code, $msg);
+        $crate::invariants!(@munch $($($rest)*)?);
+    };
+    (@munch $invariant: expr => $err_code: ident $(, $($rest: tt)*)?) => {
+        $crate::invariant!($invariant, $err_code);
+        $crate::invariants!(@munch $($($rest)*)?);
+    };
+    (@munch $invariant: expr => $err: expr, $msg: literal $(, $($rest: tt)*)?) => {
+        $crate::invariant!($invariant, $err, $msg);
+        $crate::invariants!(@munch $($($rest)*)?);
+    };
+    (@munch $invariant: expr => $err: expr $(, $($rest: tt)*)?) => {
+        $crate::invariant!($invariant, $err);
+        $crate::invariants!(@munch $($($rest)*)?);
+    };
+    (@munch $invariant: expr, $msg: literal $(, $($rest: tt)*)?) => {
+        $crate::invariant!($invariant, $msg);
+        $crate::invariants!(@munch $($($rest)*)?);
+    };
+    (@munch $invariant: expr $(, $($rest: tt)*)?) => {
+        $crate::invariant!($invariant);
+        $crate::invariants!(@munch $($($rest)*)?);
+    };
+    ($($body: tt)*) => {{
+        $crate::invariants!(@munch $($body)*);
+    }};
+}
+
+/// Implementation of [invariant_eq], [invariant_neq], [invariant_gt], [invariant_gte],
+/// [invariant_lt], and [invariant_lte]. The first token is the comparison operator.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! invariant_cmp {
+    (@check $op: tt, $left: expr, $right: expr, $err: expr, $message: expr $(,)?) => {{
+        match (&$left, &$right) {
+            (__left, __right) => {
+                if !(*__left $op *__right) {
+                    $crate::cold::fail_invariant_cmp(
+                        $message,
+                        concat!(stringify!($left), " ", stringify!($op), " ", stringify!($right)),
+                        __left,
+                        __right,
+                    );
+                    $crate::throw_err!($err);
+                }
+            }
         }
     }};
+    ($op: tt, $left: expr, $right: expr $(,)?) => {
+        $crate::invariant_cmp!($op, $left, $right, $crate::VipersError::InvariantFailed);
+    };
+    ($op: tt, $left: expr, $right: expr, $err_code: ident $(,)?) => {
+        $crate::invariant_cmp!($op, $left, $right, crate::ErrorCode::$err_code);
+    };
+    ($op: tt, $left: expr, $right: expr, $err_code: ident, $msg: expr $(,)?) => {
+        $crate::invariant_cmp!($op, $left, $right, crate::ErrorCode::$err_code, $msg);
+    };
+    ($op: tt, $left: expr, $right: expr, $msg: literal $(,)?) => {
+        $crate::invariant_cmp!(
+            @check $op,
+            $left,
+            $right,
+            $crate::VipersError::InvariantFailed,
+            $crate::cold::Message::Prefixed("Invariant failed", $msg)
+        );
+    };
+    ($op: tt, $left: expr, $right: expr, $err: expr $(,)?) => {
+        $crate::invariant_cmp!(
+            @check $op,
+            $left,
+            $right,
+            $err,
+            $crate::cold::Message::Error(&$err)
+        );
+    };
+    ($op: tt, $left: expr, $right: expr, $err: expr, $msg: expr $(,)?) => {
+        $crate::invariant_cmp!(
+            @check $op,
+            $left,
+            $right,
+            $err,
+            $crate::cold::Message::Str($msg)
+        );
+    };
+}
+
+/// Asserts that the left operand is equal to the right operand, otherwise
+/// logs the message, the comparison, and both operand values.
+///
+/// Accepts the same error and message arguments as [invariant]. Each operand is
+/// evaluated exactly once.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate vipers;
+/// # use anchor_lang::prelude::*;
+/// #[error_code]
+/// pub enum ErrorCode { MyError }
+///
+/// # fn main() {
+/// assert_does_not_throw!({
+///   invariant_eq!(1 + 1, 2);
+/// });
+/// assert_throws!({
+///   invariant_eq!(1 + 1, 3);
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_eq!(1 + 1, 3, "bad comparison");
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_eq!(1 + 1, 3, MyError);
+/// }, ErrorCode::MyError);
+/// assert_throws!({
+///   invariant_eq!(1 + 1, 3, ErrorCode::MyError, "bad comparison");
+/// }, ErrorCode::MyError);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! invariant_eq {
+    ($($args: tt)*) => {
+        $crate::invariant_cmp!(==, $($args)*)
+    };
+}
+
+/// Asserts that the left operand is not equal to the right operand, otherwise
+/// logs the message, the comparison, and both operand values.
+///
+/// Accepts the same error and message arguments as [invariant]. Each operand is
+/// evaluated exactly once.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate vipers;
+/// # use anchor_lang::prelude::*;
+/// #[error_code]
+/// pub enum ErrorCode { MyError }
+///
+/// # fn main() {
+/// assert_does_not_throw!({
+///   invariant_neq!(1 + 1, 3);
+/// });
+/// assert_throws!({
+///   invariant_neq!(1 + 1, 2);
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_neq!(1 + 1, 2, "bad comparison");
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_neq!(1 + 1, 2, MyError);
+/// }, ErrorCode::MyError);
+/// assert_throws!({
+///   invariant_neq!(1 + 1, 2, ErrorCode::MyError, "bad comparison");
+/// }, ErrorCode::MyError);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! invariant_neq {
+    ($($args: tt)*) => {
+        $crate::invariant_cmp!(!=, $($args)*)
+    };
+}
+
+/// Asserts that the left operand is greater than the right operand, otherwise
+/// logs the message, the comparison, and both operand values.
+///
+/// Accepts the same error and message arguments as [invariant]. Each operand is
+/// evaluated exactly once.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate vipers;
+/// # use anchor_lang::prelude::*;
+/// #[error_code]
+/// pub enum ErrorCode { MyError }
+///
+/// # fn main() {
+/// assert_does_not_throw!({
+///   invariant_gt!(2, 1);
+/// });
+/// assert_throws!({
+///   invariant_gt!(1, 2);
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_gt!(1, 2, "bad comparison");
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_gt!(1, 2, MyError);
+/// }, ErrorCode::MyError);
+/// assert_throws!({
+///   invariant_gt!(1, 2, ErrorCode::MyError, "bad comparison");
+/// }, ErrorCode::MyError);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! invariant_gt {
+    ($($args: tt)*) => {
+        $crate::invariant_cmp!(>, $($args)*)
+    };
+}
+
+/// Asserts that the left operand is greater than or equal to the right operand, otherwise
+/// logs the message, the comparison, and both operand values.
+///
+/// Accepts the same error and message arguments as [invariant]. Each operand is
+/// evaluated exactly once.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate vipers;
+/// # use anchor_lang::prelude::*;
+/// #[error_code]
+/// pub enum ErrorCode { MyError }
+///
+/// # fn main() {
+/// assert_does_not_throw!({
+///   invariant_gte!(2, 2);
+/// });
+/// assert_throws!({
+///   invariant_gte!(1, 2);
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_gte!(1, 2, "bad comparison");
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_gte!(1, 2, MyError);
+/// }, ErrorCode::MyError);
+/// assert_throws!({
+///   invariant_gte!(1, 2, ErrorCode::MyError, "bad comparison");
+/// }, ErrorCode::MyError);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! invariant_gte {
+    ($($args: tt)*) => {
+        $crate::invariant_cmp!(>=, $($args)*)
+    };
+}
+
+/// Asserts that the left operand is less than the right operand, otherwise
+/// logs the message, the comparison, and both operand values.
+///
+/// Accepts the same error and message arguments as [invariant]. Each operand is
+/// evaluated exactly once.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate vipers;
+/// # use anchor_lang::prelude::*;
+/// #[error_code]
+/// pub enum ErrorCode { MyError }
+///
+/// # fn main() {
+/// assert_does_not_throw!({
+///   invariant_lt!(1, 2);
+/// });
+/// assert_throws!({
+///   invariant_lt!(2, 1);
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_lt!(2, 1, "bad comparison");
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_lt!(2, 1, MyError);
+/// }, ErrorCode::MyError);
+/// assert_throws!({
+///   invariant_lt!(2, 1, ErrorCode::MyError, "bad comparison");
+/// }, ErrorCode::MyError);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! invariant_lt {
+    ($($args: tt)*) => {
+        $crate::invariant_cmp!(<, $($args)*)
+    };
+}
+
+/// Asserts that the left operand is less than or equal to the right operand, otherwise
+/// logs the message, the comparison, and both operand values.
+///
+/// Accepts the same error and message arguments as [invariant]. Each operand is
+/// evaluated exactly once.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate vipers;
+/// # use anchor_lang::prelude::*;
+/// #[error_code]
+/// pub enum ErrorCode { MyError }
+///
+/// # fn main() {
+/// assert_does_not_throw!({
+///   invariant_lte!(2, 2);
+/// });
+/// assert_throws!({
+///   invariant_lte!(2, 1);
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_lte!(2, 1, "bad comparison");
+/// }, vipers::VipersError::InvariantFailed);
+/// assert_throws!({
+///   invariant_lte!(2, 1, MyError);
+/// }, ErrorCode::MyError);
+/// assert_throws!({
+///   invariant_lte!(2, 1, ErrorCode::MyError, "bad comparison");
+/// }, ErrorCode::MyError);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! invariant_lte {
+    ($($args: tt)*) => {
+        $crate::invariant_cmp!(<=, $($args)*)
+    };
+}
+
+/// Asserts that an invariant holds exactly like [invariant], then emits an event.
+///
+/// The event expression is only evaluated if the invariant holds. This is useful for
+/// emitting metrics about business-critical invariants, e.g. a checked utilization ratio.
+///
+/// This macro is not included in the prelude, since it mixes validation with event emission.
+///
+/// # Example
+///
+/// ```ignore
+/// invariant_and_emit!(
+///     utilization <= MAX_UTILIZATION,
+///     UtilizationTooHigh,
+///     UtilizationChecked { utilization }
+/// );
+/// ```
+#[macro_export]
+macro_rules! invariant_and_emit {
+    ($invariant: expr, $err_code: ident, $event: expr $(,)?) => {
+        $crate::invariant_and_emit!($invariant, crate::ErrorCode::$err_code, $event)
+    };
+    ($invariant: expr, $err: expr, $event: expr $(,)?) => {{
+        $crate::invariant!($invariant, $err);
+        ::anchor_lang::prelude::emit!($event);
+    }};
 }
 
 /// Attempts to unwrap an [Option], and if it fails, prints an error.
@@ -668,14 +1591,162 @@ macro_rules! unwrap_opt {
     };
     ($option:expr, $err:expr, $msg: expr $(,)?) => {
         $option.ok_or_else(|| -> anchor_lang::error::Error {
-            msg!("Option unwrap failed: {:?}", $err);
-            msg!(stringify!($option));
+            $crate::cold::fail_unwrap_opt(&$err, stringify!($option));
             $crate::log_code_location!();
             anchor_lang::prelude::error!($err)
         })?
     };
 }
 
+/// Unwraps an optional account, throwing if it was not provided.
+///
+/// This evaluates to a reference to the account, so it should be given a place
+/// such as `ctx.accounts.referrer` rather than a temporary.
+/// The name of the missing account is logged.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// #[error_code]
+/// pub enum ErrorCode { MissingReferrer }
+///
+/// # fn main() {
+/// let referrer: Option<Pubkey> = Some(Pubkey::new_unique());
+/// assert_does_not_throw!({
+///   let referrer: &Pubkey = unwrap_optional_account!(referrer, MissingReferrer);
+/// });
+///
+/// let referrer: Option<Pubkey> = None;
+/// assert_throws!({
+///   unwrap_optional_account!(referrer, MissingReferrer);
+/// }, ErrorCode::MissingReferrer);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! unwrap_optional_account {
+    ($account: expr $(,)?) => {
+        $crate::unwrap_optional_account!($account, $crate::VipersError::OptionUnwrapFailed)
+    };
+    ($account: expr, $err_code: ident $(,)?) => {
+        $crate::unwrap_optional_account!($account, crate::ErrorCode::$err_code)
+    };
+    ($account: expr, $err: expr $(,)?) => {
+        match $account {
+            ::core::option::Option::Some(ref __account) => __account,
+            ::core::option::Option::None => {
+                msg!("Missing optional account: {}", stringify!($account));
+                $crate::throw_err!($err)
+            }
+        }
+    };
+}
+
+/// Unwraps an optional account, returning `Ok(())` from the enclosing function if it was not provided.
+///
+/// This is useful for instructions which become a no-op when an optional account is absent.
+/// The skipped account is logged. This works in functions returning either [Result] or
+/// [anchor_lang::solana_program::entrypoint::ProgramResult].
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// fn apply_fee(config: Option<u64>, amount: &mut u64) -> Result<()> {
+///     let fee = skip_if_none!(config);
+///     *amount -= *fee;
+///     Ok(())
+/// }
+///
+/// # fn main() -> Result<()> {
+/// let mut amount = 100;
+/// apply_fee(None, &mut amount)?;
+/// assert_eq!(amount, 100);
+/// apply_fee(Some(3), &mut amount)?;
+/// assert_eq!(amount, 97);
+/// # Ok(()) }
+/// ```
+#[macro_export]
+macro_rules! skip_if_none {
+    ($account: expr $(,)?) => {
+        match $account {
+            ::core::option::Option::Some(ref __account) => __account,
+            ::core::option::Option::None => {
+                msg!(
+                    "Skipping instruction: {} was not provided",
+                    stringify!($account)
+                );
+                return Ok(());
+            }
+        }
+    };
+}
+
+/// Runs a validation block against an optional account, only if the account was provided.
+///
+/// The block is expanded inline rather than called as a closure, so errors thrown
+/// from within it (via `?` or any of the assertion macros) return from the enclosing function.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// # fn main() {
+/// let mint = Pubkey::new_unique();
+/// let referrer_mint: Option<Pubkey> = None;
+/// assert_does_not_throw!({
+///   with_optional_account!(referrer_mint, |acc| {
+///     assert_keys_eq!(acc, mint);
+///   });
+/// });
+///
+/// let referrer_mint: Option<Pubkey> = Some(Pubkey::new_unique());
+/// assert_throws!({
+///   with_optional_account!(referrer_mint, |acc| {
+///     assert_keys_eq!(acc, mint);
+///   });
+/// }, vipers::VipersError::KeyMismatch);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! with_optional_account {
+    ($account: expr, |$acc: ident| $body: expr $(,)?) => {
+        if let ::core::option::Option::Some(ref $acc) = $account {
+            $body;
+        }
+    };
+}
+
+/// Reloads an account from its underlying [AccountInfo], evaluating to a reference
+/// to the reloaded account.
+///
+/// Anchor deserializes accounts once, so the data of an `Account` is stale after a CPI
+/// which modifies it, e.g. a token transfer. This works for both `Account` and
+/// `Box<Account>`. If the account fails to deserialize, its name is logged and the
+/// error is returned.
+///
+/// # Example
+///
+/// ```ignore
+/// token::transfer(cpi_ctx, amount)?;
+/// let vault_amount = reload_and_get!(ctx.accounts.vault).amount;
+/// ```
+#[macro_export]
+macro_rules! reload_and_get {
+    ($account: expr $(,)?) => {{
+        let __account = &mut $account;
+        __account.reload().map_err(|__err| {
+            msg!("Failed to reload account: {}", stringify!($account));
+            $crate::log_code_location!();
+            __err
+        })?;
+        &*__account
+    }};
+}
+
 /// Asserts that two accounts share the same key.
 ///
 /// Deprecated in favor of [assert_keys_eq].
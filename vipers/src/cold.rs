@@ -0,0 +1,160 @@
+//! Out-of-line failure paths for the assertion macros.
+//!
+//! Each macro expansion only passes references and string literals to these functions,
+//! wrapped in a [Message] where the assertion accepts a custom message or error. The
+//! formatting code for the failure logs is thus emitted once per program rather than
+//! at every call site. The errors themselves are still constructed at the call site,
+//! so that Anchor records the location of the macro invocation as the error origin.
+
+use anchor_lang::prelude::*;
+use core::fmt::{Debug, Display};
+use core::panic::Location;
+
+/// The message logged by a failed assertion.
+///
+/// Messages are only formatted within [Message::log], so that the formatting code
+/// is not emitted at each call site.
+#[derive(Clone, Copy, Debug)]
+pub enum Message<'a> {
+    /// A message which is logged as-is.
+    Str(&'a str),
+    /// A message which is logged after a prefix, as `{prefix}: {message}`.
+    Prefixed(&'static str, &'a str),
+    /// An error, logged as `{error:?}: {error}`, like [crate::format_err].
+    Error(&'a dyn ErrorMessage),
+}
+
+/// An error which may be logged via [Message::Error].
+pub trait ErrorMessage: Debug + Display {}
+
+impl<T: Debug + Display + ?Sized> ErrorMessage for T {}
+
+impl Message<'_> {
+    /// Logs the message.
+    #[cold]
+    #[inline(never)]
+    pub fn log(self) {
+        match self {
+            Message::Str(message) => msg!(message),
+            Message::Prefixed(prefix, message) => msg!("{}: {}", prefix, message),
+            Message::Error(err) => msg!("{:?}: {}", err, err),
+        }
+    }
+}
+
+/// Logs where in the code an error was thrown.
+#[cold]
+#[inline(never)]
+pub fn log_code_location(location: &Location) {
+    msg!("Error thrown at {}:{}", location.file(), location.line());
+}
+
+/// Logs a failed key comparison from [crate::assert_keys_eq] or [crate::assert_keys_neq].
+#[cold]
+#[inline(never)]
+pub fn fail_keys(msg: Message, expr: &str, left: &Pubkey, right: &Pubkey) {
+    msg.log();
+    msg!(expr);
+    msg!("Left: {}", left);
+    msg!("Right: {}", right);
+}
+
+/// Logs a failed [crate::invariant].
+#[cold]
+#[inline(never)]
+pub fn fail_invariant(msg: Message, expr: &str) {
+    msg.log();
+    msg!(expr);
+}
+
+/// Logs a failed comparison from [crate::invariant_eq] and related macros.
+#[cold]
+#[inline(never)]
+pub fn fail_invariant_cmp<L: Debug + ?Sized, R: Debug + ?Sized>(
+    msg: Message,
+    expr: &str,
+    left: &L,
+    right: &R,
+) {
+    msg.log();
+    msg!(expr);
+    msg!("Left: {:?}", left);
+    msg!("Right: {:?}", right);
+}
+
+/// Logs a failed [crate::unwrap_opt].
+#[cold]
+#[inline(never)]
+pub fn fail_unwrap_opt<E: Debug + ?Sized>(err: &E, expr: &str) {
+    msg!("Option unwrap failed: {:?}", err);
+    msg!(expr);
+}
+
+/// Logs the operands of a failed [crate::math::CheckedMathExt] operation.
+#[cold]
+#[inline(never)]
+pub fn fail_checked_math<T: Display>(lhs: T, op: &str, rhs: T) {
+    msg!("Integer overflow: {} {} {}", lhs, op, rhs);
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::testing::capture_logs;
+    use crate::VipersError;
+
+    #[test]
+    fn test_fail_keys() {
+        let (left, right) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ((), logs) = capture_logs(|| {
+            fail_keys(
+                Message::Prefixed("Key mismatch", "vault"),
+                "a != b",
+                &left,
+                &right,
+            )
+        });
+        assert_eq!(
+            logs,
+            vec![
+                "Program log: Key mismatch: vault".to_string(),
+                "Program log: a != b".to_string(),
+                format!("Program log: Left: {}", left),
+                format!("Program log: Right: {}", right),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_message() {
+        let ((), logs) = capture_logs(|| {
+            Message::Str("plain").log();
+            Message::Prefixed("Invariant failed", "too large").log();
+            Message::Error(&VipersError::InvariantFailed).log();
+        });
+        assert_eq!(
+            logs,
+            vec![
+                "Program log: plain".to_string(),
+                "Program log: Invariant failed: too large".to_string(),
+                format!(
+                    "Program log: {}",
+                    crate::format_err!(VipersError::InvariantFailed)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fail_unwrap_opt() {
+        let ((), logs) = capture_logs(|| fail_unwrap_opt(&VipersError::IntegerOverflow, "a + b"));
+        assert_eq!(
+            logs,
+            vec![
+                "Program log: Option unwrap failed: IntegerOverflow",
+                "Program log: a + b"
+            ]
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Fixed-capacity collections for use in accounts.
+
+use crate::VipersError;
+use anchor_lang::prelude::*;
+use std::io::Write;
+
+/// A map from [Pubkey] to `V` with a fixed capacity of `N` entries.
+///
+/// This is backed by an array of `(Pubkey, V)` slots, so it has a fixed serialized size
+/// and can be stored directly in an account. A slot with the default (zeroed) key is empty,
+/// so the default [Pubkey] may not be used as a key.
+///
+/// Entries are iterated in slot order. Inserting a new key fills the first empty slot,
+/// so iteration order is deterministic for a given sequence of operations.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # fn main() -> Result<()> {
+/// use vipers::collections::SmallKeyMap;
+///
+/// let mint = Pubkey::new_unique();
+/// let mut fee_overrides: SmallKeyMap<u16, 4> = SmallKeyMap::default();
+/// fee_overrides.insert(mint, 30)?;
+/// assert_eq!(fee_overrides.get(&mint), Some(&30));
+/// assert_eq!(fee_overrides.remove(&mint)?, 30);
+/// assert!(fee_overrides.is_empty());
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmallKeyMap<V, const N: usize> {
+    entries: [(Pubkey, V); N],
+}
+
+impl<V: Default + Copy, const N: usize> Default for SmallKeyMap<V, N> {
+    fn default() -> Self {
+        Self {
+            entries: [(Pubkey::default(), V::default()); N],
+        }
+    }
+}
+
+impl<V: Default + Copy, const N: usize> SmallKeyMap<V, N> {
+    /// Maximum number of entries in the map.
+    pub const CAPACITY: usize = N;
+
+    /// Number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns true if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Returns true if the map contains the given key.
+    pub fn contains_key(&self, key: &Pubkey) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Gets the value for the given key.
+    pub fn get(&self, key: &Pubkey) -> Option<&V> {
+        self.position(key).map(|index| &self.entries[index].1)
+    }
+
+    /// Gets a mutable reference to the value for the given key.
+    pub fn get_mut(&mut self, key: &Pubkey) -> Option<&mut V> {
+        self.position(key)
+            .map(move |index| &mut self.entries[index].1)
+    }
+
+    /// Gets the value for the given key, throwing [VipersError::EntryNotFound] if it is missing.
+    #[track_caller]
+    pub fn get_or_err(&self, key: &Pubkey) -> Result<&V> {
+        match self.get(key) {
+            Some(value) => Ok(value),
+            None => {
+                msg!("Entry not found: {}", key);
+                crate::throw_err!(VipersError::EntryNotFound)
+            }
+        }
+    }
+
+    /// Inserts a value for the given key.
+    ///
+    /// If the key is already present, its value is replaced in place.
+    /// Otherwise, the value is stored in the first empty slot, throwing
+    /// [VipersError::CollectionFull] if there is none.
+    #[track_caller]
+    pub fn insert(&mut self, key: Pubkey, value: V) -> Result<()> {
+        crate::invariant!(
+            key != Pubkey::default(),
+            VipersError::InvariantFailed,
+            "SmallKeyMap key must not be the default pubkey"
+        );
+        if let Some(existing) = self.get_mut(&key) {
+            *existing = value;
+            return Ok(());
+        }
+        match self.position(&Pubkey::default()) {
+            Some(index) => {
+                self.entries[index] = (key, value);
+                Ok(())
+            }
+            None => {
+                msg!("SmallKeyMap is full ({} entries): cannot insert {}", N, key);
+                crate::throw_err!(VipersError::CollectionFull)
+            }
+        }
+    }
+
+    /// Removes the entry for the given key, returning its value.
+    ///
+    /// Throws [VipersError::EntryNotFound] if the key is missing.
+    #[track_caller]
+    pub fn remove(&mut self, key: &Pubkey) -> Result<V> {
+        if *key != Pubkey::default() {
+            if let Some(index) = self.position(key) {
+                let (_, value) = std::mem::take(&mut self.entries[index]);
+                return Ok(value);
+            }
+        }
+        msg!("Entry not found: {}", key);
+        crate::throw_err!(VipersError::EntryNotFound)
+    }
+
+    /// Iterates over the entries of the map in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Pubkey, &V)> {
+        self.entries
+            .iter()
+            .filter(|(key, _)| *key != Pubkey::default())
+            .map(|(key, value)| (key, value))
+    }
+
+    /// Finds the slot index of the given key.
+    fn position(&self, key: &Pubkey) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(entry_key, _)| crate::keys_eq(entry_key, key))
+    }
+}
+
+impl<V: AnchorSerialize, const N: usize> AnchorSerialize for SmallKeyMap<V, N> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        for entry in self.entries.iter() {
+            entry.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: AnchorDeserialize + Default + Copy, const N: usize> AnchorDeserialize
+    for SmallKeyMap<V, N>
+{
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let mut map = Self::default();
+        for entry in map.entries.iter_mut() {
+            *entry = AnchorDeserialize::deserialize(buf)?;
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::{assert_does_not_throw, assert_throws};
+
+    #[test]
+    fn test_insert_get_remove() {
+        let key = Pubkey::new_unique();
+        let mut map: SmallKeyMap<u64, 2> = SmallKeyMap::default();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&key), None);
+        assert_throws!(
+            {
+                map.get_or_err(&key)?;
+            },
+            VipersError::EntryNotFound
+        );
+        assert_does_not_throw!({
+            map.insert(key, 1)?;
+        });
+        assert_eq!(map.get(&key), Some(&1));
+        assert_eq!(*map.get_or_err(&key).unwrap(), 1);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.remove(&key).unwrap(), 1);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_insert_duplicate_replaces() {
+        let key = Pubkey::new_unique();
+        let mut map: SmallKeyMap<u64, 1> = SmallKeyMap::default();
+        map.insert(key, 1).unwrap();
+        map.insert(key, 2).unwrap();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&key), Some(&2));
+    }
+
+    #[test]
+    fn test_insert_full() {
+        let mut map: SmallKeyMap<u64, 2> = SmallKeyMap::default();
+        map.insert(Pubkey::new_unique(), 1).unwrap();
+        map.insert(Pubkey::new_unique(), 2).unwrap();
+        assert_throws!(
+            {
+                map.insert(Pubkey::new_unique(), 3)?;
+            },
+            VipersError::CollectionFull
+        );
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_insert_default_key() {
+        let mut map: SmallKeyMap<u64, 2> = SmallKeyMap::default();
+        assert_throws!(
+            {
+                map.insert(Pubkey::default(), 1)?;
+            },
+            VipersError::InvariantFailed
+        );
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_remove_missing() {
+        let mut map: SmallKeyMap<u64, 2> = SmallKeyMap::default();
+        map.insert(Pubkey::new_unique(), 1).unwrap();
+        assert_throws!(
+            {
+                map.remove(&Pubkey::new_unique())?;
+            },
+            VipersError::EntryNotFound
+        );
+        assert_throws!(
+            {
+                map.remove(&Pubkey::default())?;
+            },
+            VipersError::EntryNotFound
+        );
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_iteration_order() {
+        let keys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let mut map: SmallKeyMap<u64, 3> = SmallKeyMap::default();
+        for (i, key) in keys.iter().enumerate() {
+            map.insert(*key, i as u64).unwrap();
+        }
+        map.remove(&keys[0]).unwrap();
+        let new_key = Pubkey::new_unique();
+        map.insert(new_key, 3).unwrap();
+        let entries: Vec<(Pubkey, u64)> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(entries, vec![(new_key, 3), (keys[1], 1), (keys[2], 2)]);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let key = Pubkey::new_unique();
+        let mut map: SmallKeyMap<u16, 3> = SmallKeyMap::default();
+        map.insert(key, 30).unwrap();
+
+        let bytes = map.try_to_vec().unwrap();
+        assert_eq!(bytes.len(), 3 * (32 + 2));
+
+        // layout is identical to the backing array
+        let array: [(Pubkey, u16); 3] = [(key, 30), (Pubkey::default(), 0), (Pubkey::default(), 0)];
+        let array_bytes: Vec<u8> = array.iter().flat_map(|e| e.try_to_vec().unwrap()).collect();
+        assert_eq!(bytes, array_bytes);
+
+        let decoded = SmallKeyMap::<u16, 3>::try_from_slice(&bytes).unwrap();
+        assert_eq!(decoded, map);
+        assert!(SmallKeyMap::<u16, 3>::try_from_slice(&bytes[1..]).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+//! Comparisons of raw account data.
+
+use anchor_lang::prelude::*;
+
+/// Number of bytes compared per `sol_memcmp` call by [first_difference].
+pub const MEMCMP_CHUNK_SIZE: usize = 256;
+
+/// Checks if two byte slices of equal length are equal.
+///
+/// On-chain, this uses the `sol_memcmp` syscall. Off-chain, this falls back to `==`.
+#[inline(always)]
+fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    #[cfg(target_arch = "bpf")]
+    {
+        anchor_lang::solana_program::program_memory::sol_memcmp(a, b, a.len()) == 0
+    }
+    #[cfg(not(target_arch = "bpf"))]
+    {
+        a == b
+    }
+}
+
+/// Returns the offset of the first byte at which two slices differ, comparing
+/// up to the length of the shorter slice.
+///
+/// The slices are compared in chunks of [MEMCMP_CHUNK_SIZE] bytes, and only the first
+/// differing chunk is searched byte by byte.
+///
+/// # Example
+///
+/// ```
+/// use vipers::data::first_difference;
+///
+/// assert_eq!(first_difference(&[1, 2, 3], &[1, 2, 3]), None);
+/// assert_eq!(first_difference(&[1, 2, 3], &[1, 5, 3]), Some(1));
+/// ```
+pub fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    let len = a.len().min(b.len());
+    let (a, b) = (&a[..len], &b[..len]);
+    a.chunks(MEMCMP_CHUNK_SIZE)
+        .zip(b.chunks(MEMCMP_CHUNK_SIZE))
+        .enumerate()
+        .find(|(_, (chunk_a, chunk_b))| !bytes_eq(chunk_a, chunk_b))
+        .and_then(|(index, (chunk_a, chunk_b))| {
+            let offset = chunk_a
+                .iter()
+                .zip(chunk_b.iter())
+                .position(|(x, y)| x != y)?;
+            Some(index * MEMCMP_CHUNK_SIZE + offset)
+        })
+}
+
+/// Checks that the data of two accounts is equal, throwing
+/// [crate::VipersError::AccountDataMismatch] otherwise.
+///
+/// The first `skip` bytes are ignored, e.g. to skip the discriminator. If `len` is [None],
+/// the remainder of the data is compared, and both accounts must have the same data length.
+/// Otherwise, only `len` bytes are compared, and the range must be within the data
+/// of both accounts.
+///
+/// This is the check performed by [crate::assert_account_data_eq].
+#[track_caller]
+pub fn check_account_data_eq(
+    a: &AccountInfo,
+    b: &AccountInfo,
+    skip: usize,
+    len: Option<usize>,
+) -> Result<()> {
+    let data_a = a.try_borrow_data()?;
+    let data_b = b.try_borrow_data()?;
+    let end = match len {
+        Some(len) => skip.checked_add(len),
+        None => {
+            if data_a.len() != data_b.len() {
+                msg!(
+                    "Account data length mismatch: {} (left) != {} (right)",
+                    data_a.len(),
+                    data_b.len()
+                );
+                crate::throw_err!(crate::VipersError::AccountDataMismatch);
+            }
+            Some(data_a.len())
+        }
+    };
+    let range = match end {
+        Some(end) if skip <= end && end <= data_a.len() && end <= data_b.len() => skip..end,
+        _ => {
+            msg!(
+                "Account data range out of bounds: skip {}, len {:?}; data lengths {} (left), {} (right)",
+                skip,
+                len,
+                data_a.len(),
+                data_b.len()
+            );
+            crate::throw_err!(crate::VipersError::AccountDataMismatch);
+        }
+    };
+    if let Some(offset) = first_difference(&data_a[range.clone()], &data_b[range.clone()]) {
+        msg!(
+            "Account data mismatch at offset {}: {} (left) != {} (right)",
+            range.start + offset,
+            data_a[range.start + offset],
+            data_b[range.start + offset]
+        );
+        msg!("Left: {}", a.key);
+        msg!("Right: {}", b.key);
+        crate::throw_err!(crate::VipersError::AccountDataMismatch);
+    }
+    Ok(())
+}
+
+/// Asserts that the data of two accounts is equal, throwing
+/// [crate::VipersError::AccountDataMismatch] and logging the first differing offset otherwise.
+///
+/// By default, the full data is compared, and the data lengths must match. A number of
+/// leading bytes may be skipped via `skip = n`, and the comparison may be limited to
+/// a range via `len = n`. See [check_account_data_eq].
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// # fn main() -> Result<()> {
+/// # let (key_a, key_b, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+/// # let (mut lamports_a, mut lamports_b) = (0, 0);
+/// let mut data_a = [1, 1, 1, 1, 2, 3, 4];
+/// let mut data_b = [9, 9, 9, 9, 2, 3, 5];
+/// let a = AccountInfo::new(&key_a, false, false, &mut lamports_a, &mut data_a, &owner, false, 0);
+/// let b = AccountInfo::new(&key_b, false, false, &mut lamports_b, &mut data_b, &owner, false, 0);
+///
+/// assert_account_data_eq!(a, b, skip = 4, len = 2);
+/// assert_throws!({
+///     assert_account_data_eq!(a, b, skip = 4);
+/// }, vipers::VipersError::AccountDataMismatch);
+/// # Ok(()) }
+/// ```
+#[macro_export]
+macro_rules! assert_account_data_eq {
+    ($account_a: expr, $account_b: expr $(,)?) => {
+        $crate::assert_account_data_eq!($account_a, $account_b, skip = 0)
+    };
+    ($account_a: expr, $account_b: expr, len = $len: expr $(,)?) => {
+        $crate::assert_account_data_eq!($account_a, $account_b, skip = 0, len = $len)
+    };
+    ($account_a: expr, $account_b: expr, skip = $skip: expr $(,)?) => {
+        $crate::data::check_account_data_eq(
+            &::anchor_lang::ToAccountInfo::to_account_info(&$account_a),
+            &::anchor_lang::ToAccountInfo::to_account_info(&$account_b),
+            $skip,
+            ::core::option::Option::None,
+        )?;
+    };
+    ($account_a: expr, $account_b: expr, skip = $skip: expr, len = $len: expr $(,)?) => {
+        $crate::data::check_account_data_eq(
+            &::anchor_lang::ToAccountInfo::to_account_info(&$account_a),
+            &::anchor_lang::ToAccountInfo::to_account_info(&$account_b),
+            $skip,
+            ::core::option::Option::Some($len),
+        )?;
+    };
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::testing::{capture_logs, with_account};
+    use crate::{assert_does_not_throw, assert_throws, VipersError};
+
+    /// Runs `f` with two accounts holding the given data.
+    fn with_accounts<R>(
+        data_a: Vec<u8>,
+        data_b: Vec<u8>,
+        f: impl FnOnce(&AccountInfo, &AccountInfo) -> R,
+    ) -> R {
+        let owner = Pubkey::new_unique();
+        with_account(&owner, data_a, |a| {
+            with_account(&owner, data_b, |b| f(a, b))
+        })
+    }
+
+    #[test]
+    fn test_first_difference() {
+        let a: Vec<u8> = (0..1_000).map(|i| i as u8).collect();
+        assert_eq!(first_difference(&a, &a), None);
+        assert_eq!(first_difference(&[], &[]), None);
+        for offset in [0, 1, MEMCMP_CHUNK_SIZE - 1, MEMCMP_CHUNK_SIZE, 999] {
+            let mut b = a.clone();
+            b[offset] ^= 0xff;
+            b[999] ^= 0x0f;
+            assert_eq!(first_difference(&a, &b), Some(offset));
+        }
+        // only the common prefix is compared
+        assert_eq!(first_difference(&a[..10], &a), None);
+    }
+
+    #[test]
+    fn test_equal_data() {
+        let data: Vec<u8> = (0..600).map(|i| i as u8).collect();
+        with_accounts(data.clone(), data, |a, b| {
+            assert_does_not_throw!({
+                assert_account_data_eq!(a, b);
+                assert_account_data_eq!(a, b, skip = 8);
+                assert_account_data_eq!(a, b, len = 100);
+                assert_account_data_eq!(a, b, skip = 8, len = 100);
+                assert_account_data_eq!(a, a);
+            });
+        });
+    }
+
+    #[test]
+    fn test_skipped_discriminator() {
+        let mut data_a = vec![7; 300];
+        let mut data_b = vec![7; 300];
+        data_a[..8].copy_from_slice(&[1; 8]);
+        data_b[..8].copy_from_slice(&[2; 8]);
+        with_accounts(data_a, data_b, |a, b| {
+            assert_does_not_throw!({
+                assert_account_data_eq!(a, b, skip = 8);
+                assert_account_data_eq!(a, b, skip = 8, len = 292);
+            });
+            let (result, logs) = capture_logs(|| -> Result<()> {
+                assert_account_data_eq!(a, b);
+                Ok(())
+            });
+            assert!(result.is_err());
+            assert_eq!(
+                logs[..3],
+                [
+                    "Program log: Account data mismatch at offset 0: 1 (left) != 2 (right)"
+                        .to_string(),
+                    format!("Program log: Left: {}", a.key),
+                    format!("Program log: Right: {}", b.key),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_mismatch_offset() {
+        let data_a = vec![0; 600];
+        let mut data_b = vec![0; 600];
+        data_b[300] = 1;
+        with_accounts(data_a, data_b, |a, b| {
+            assert_does_not_throw!({
+                assert_account_data_eq!(a, b, len = 300);
+                assert_account_data_eq!(a, b, skip = 301);
+            });
+            let (result, logs) = capture_logs(|| -> Result<()> {
+                assert_account_data_eq!(a, b, skip = 8, len = 500);
+                Ok(())
+            });
+            assert!(result.is_err());
+            assert_eq!(
+                logs[0],
+                "Program log: Account data mismatch at offset 300: 0 (left) != 1 (right)"
+            );
+        });
+    }
+
+    #[test]
+    fn test_length_mismatch() {
+        with_accounts(vec![0; 100], vec![0; 101], |a, b| {
+            let (result, logs) = capture_logs(|| -> Result<()> {
+                assert_account_data_eq!(a, b, skip = 8);
+                Ok(())
+            });
+            assert!(result.is_err());
+            assert_eq!(
+                logs[0],
+                "Program log: Account data length mismatch: 100 (left) != 101 (right)"
+            );
+            // an explicit range within both accounts may be compared
+            assert_does_not_throw!({
+                assert_account_data_eq!(a, b, skip = 8, len = 92);
+            });
+        });
+    }
+
+    #[test]
+    fn test_empty_ranges() {
+        with_accounts(vec![1; 10], vec![2; 10], |a, b| {
+            assert_does_not_throw!({
+                assert_account_data_eq!(a, b, len = 0);
+                assert_account_data_eq!(a, b, skip = 10, len = 0);
+                assert_account_data_eq!(a, b, skip = 10);
+            });
+        });
+        with_accounts(vec![], vec![], |a, b| {
+            assert_does_not_throw!({
+                assert_account_data_eq!(a, b);
+            });
+        });
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        with_accounts(vec![0; 100], vec![0; 50], |a, b| {
+            for (skip, len) in [(0, 51), (50, 1), (101, 0), (1, usize::MAX)] {
+                assert_throws!(
+                    {
+                        assert_account_data_eq!(a, b, skip = skip, len = len);
+                    },
+                    VipersError::AccountDataMismatch
+                );
+            }
+            assert_does_not_throw!({
+                assert_account_data_eq!(a, b, skip = 10, len = 40);
+            });
+        });
+        with_accounts(vec![0; 10], vec![0; 10], |a, b| {
+            let (result, logs) = capture_logs(|| -> Result<()> {
+                assert_account_data_eq!(a, b, skip = 11);
+                Ok(())
+            });
+            assert!(result.is_err());
+            assert_eq!(
+                logs[0],
+                "Program log: Account data range out of bounds: skip 11, len None; data lengths 10 (left), 10 (right)"
+            );
+        });
+    }
+}
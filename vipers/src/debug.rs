@@ -0,0 +1,111 @@
+//! Checks which only run in debug builds.
+//!
+//! [crate::debug_invariant] is to [crate::invariant] what `debug_assert!` is to `assert!`:
+//! it is checked when `debug_assertions` are enabled in the calling crate, or when
+//! the `debug-checks` feature is enabled. Otherwise, the condition is type-checked
+//! but never evaluated, so expensive checks cost nothing in release builds.
+
+/// Asserts that an invariant holds exactly like [crate::invariant], but only in debug builds.
+///
+/// The check runs if `debug_assertions` are enabled or with the `debug-checks` feature.
+/// Otherwise, neither the condition nor the error is evaluated, so the condition
+/// must not have side effects that the program relies on.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() -> Result<()> {
+/// let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+/// debug_invariant!(keys[0] != keys[1], "duplicate keys");
+/// # Ok(()) }
+/// ```
+#[cfg(feature = "debug-checks")]
+#[macro_export]
+macro_rules! debug_invariant {
+    ($($args: tt)*) => {
+        $crate::invariant!($($args)*)
+    };
+}
+
+/// Asserts that an invariant holds exactly like [crate::invariant], but only in debug builds.
+///
+/// The check runs if `debug_assertions` are enabled or with the `debug-checks` feature.
+/// Otherwise, neither the condition nor the error is evaluated, so the condition
+/// must not have side effects that the program relies on.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() -> Result<()> {
+/// let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+/// debug_invariant!(keys[0] != keys[1], "duplicate keys");
+/// # Ok(()) }
+/// ```
+#[cfg(not(feature = "debug-checks"))]
+#[macro_export]
+macro_rules! debug_invariant {
+    ($($args: tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::invariant!($($args)*);
+        }
+    };
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use crate::{assert_does_not_throw, assert_throws, VipersError};
+    use anchor_lang::prelude::*;
+
+    #[error_code]
+    pub enum ErrorCode {
+        Duplicate,
+    }
+
+    #[test]
+    #[cfg(any(debug_assertions, feature = "debug-checks"))]
+    fn test_debug_invariant_checked() {
+        assert_does_not_throw!({
+            debug_invariant!(1 < 2);
+        });
+        assert_throws!(
+            {
+                debug_invariant!(2 < 1);
+            },
+            VipersError::InvariantFailed
+        );
+        assert_throws!(
+            {
+                debug_invariant!(2 < 1, "expensive check");
+            },
+            VipersError::InvariantFailed
+        );
+        assert_throws!(
+            {
+                debug_invariant!(2 < 1, ErrorCode::Duplicate, "duplicate");
+            },
+            ErrorCode::Duplicate
+        );
+    }
+
+    #[test]
+    #[cfg(not(any(debug_assertions, feature = "debug-checks")))]
+    fn test_debug_invariant_compiled_out() {
+        let (result, logs) = crate::testing::capture_logs(|| -> Result<()> {
+            debug_invariant!(
+                {
+                    panic!("the condition should not be evaluated");
+                    #[allow(unreachable_code)]
+                    false
+                },
+                ErrorCode::Duplicate
+            );
+            debug_invariant!(false, "never checked");
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert!(logs.is_empty());
+    }
+}
@@ -29,6 +29,32 @@ pub enum VipersError {
     TokenAccountIsNonZero,
     #[msg("Bump not found.")]
     UnknownBump,
+    #[msg("Insufficient token balance.")]
+    InsufficientBalance,
+    #[msg("The destination is a well-known unspendable address.")]
+    UnspendableDestination,
+    #[msg("Mints do not match.")]
+    MintMismatch,
+    #[msg("Entry not found.")]
+    EntryNotFound,
+    #[msg("Collection is full.")]
+    CollectionFull,
+    #[msg("Insufficient stack headroom.")]
+    InsufficientStackHeadroom,
+    #[msg("The provided account is not the expected sysvar.")]
+    InvalidSysvar,
+    #[msg("Account is not rent-exempt.")]
+    NotRentExempt,
+    #[msg("Precision loss.")]
+    PrecisionLoss,
+    #[msg("The mint has no authority set.")]
+    MintHasNoAuthority,
+    #[msg("The protocol is paused.")]
+    ProtocolPaused,
+    #[msg("Unexpected change in token account balance.")]
+    UnexpectedBalanceChange,
+    #[msg("Account data does not match.")]
+    AccountDataMismatch,
 }
 
 /// Conversions into a [CmpError].
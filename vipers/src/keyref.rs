@@ -15,6 +15,12 @@ impl AsKeyRef for Pubkey {
     }
 }
 
+impl<T: AsKeyRef + ?Sized> AsKeyRef for &T {
+    fn as_key_ref(&self) -> &Pubkey {
+        (**self).as_key_ref()
+    }
+}
+
 impl<'info, T: AccountSerialize + AccountDeserialize + Owner + Clone> AsKeyRef
     for Box<Account<'info, T>>
 {
@@ -4,17 +4,33 @@
 #![allow(rustdoc::missing_doc_code_examples)]
 
 pub mod assert;
+pub mod cold;
+pub mod collections;
+pub mod data;
+pub mod debug;
 mod error;
 mod keyref;
+pub mod math;
+pub mod pause;
+pub mod precondition;
+pub mod program_ids;
+pub mod rent;
+pub mod seeds;
+pub mod spl;
+pub mod stack;
+#[cfg(not(target_arch = "bpf"))]
+pub mod testing;
 pub mod validate;
+pub mod well_known;
 
 use anchor_lang::prelude::*;
 pub use error::*;
 pub use keyref::AsKeyRef;
+pub use math::CheckedMathExt;
 #[cfg(feature = "spl-associated-token-account")]
 pub use spl_associated_token_account as ata;
 
-pub use validate::Validate;
+pub use validate::{Validate, ValidateArgs};
 
 declare_id!("VipersTest111111111111111111111111111111111");
 
@@ -46,6 +62,37 @@ pub fn validate_derived_address(
     }
 }
 
+/// Checks if two keys are equal.
+///
+/// On-chain, this uses the `sol_memcmp` syscall, which is cheaper than the
+/// byte-by-byte comparison of [Pubkey]'s [PartialEq] implementation.
+/// Off-chain, this falls back to `==`.
+///
+/// # Example
+///
+/// ```
+/// use anchor_lang::prelude::*;
+/// let a = Pubkey::new_unique();
+/// let b = Pubkey::new_unique();
+/// assert!(vipers::keys_eq(&a, &a));
+/// assert!(!vipers::keys_eq(&a, &b));
+/// ```
+#[inline(always)]
+pub fn keys_eq(a: &Pubkey, b: &Pubkey) -> bool {
+    #[cfg(target_arch = "bpf")]
+    {
+        anchor_lang::solana_program::program_memory::sol_memcmp(
+            a.as_ref(),
+            b.as_ref(),
+            anchor_lang::solana_program::pubkey::PUBKEY_BYTES,
+        ) == 0
+    }
+    #[cfg(not(target_arch = "bpf"))]
+    {
+        a == b
+    }
+}
+
 /// Helper for getting the current timestamp.
 pub fn now_i64() -> Result<i64> {
     Ok(Clock::get()?.unix_timestamp)
@@ -62,8 +109,14 @@ pub mod prelude {
     //! The prelude contains all commonly used components of the crate. All programs should include it via `use vipers::prelude::*;`.
 
     pub use super::{
-        assert_is_zero_token_account, assert_keys_eq, assert_keys_neq, invariant, now, now_i64,
-        try_or_err, unwrap_bump, unwrap_checked, unwrap_int, unwrap_opt, unwrap_opt_block,
-        unwrap_or_err, AsKeyRef, CmpError, IntoCmpError, Validate, VipersError,
+        assert_account_data_eq, assert_balance_change, assert_bump_eq, assert_cached_ata,
+        assert_freeze_authority, assert_is_zero_token_account, assert_keys_eq, assert_keys_neq,
+        assert_mint_authority, assert_not_paused, assert_not_unspendable, assert_precondition,
+        assert_rent_exempt, assert_token_program, debug_invariant, invariant, invariant_eq,
+        invariant_gt, invariant_gte, invariant_lt, invariant_lte, invariant_neq, invariants,
+        load_rent, now, now_i64, reload_and_get, seeds, signer_seeds, skip_if_none, try_or_err,
+        unwrap_bump, unwrap_checked, unwrap_int, unwrap_opt, unwrap_opt_block,
+        unwrap_optional_account, unwrap_or_err, validate_with_args, with_optional_account,
+        AsKeyRef, CheckedMathExt, CmpError, IntoCmpError, Validate, ValidateArgs, VipersError,
     };
 }
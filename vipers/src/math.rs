@@ -0,0 +1,344 @@
+//! Checked arithmetic on token amounts.
+
+use crate::VipersError;
+use anchor_lang::prelude::*;
+use std::io::Write;
+
+/// Converts a raw `amount` with `from_decimals` decimals into the equivalent raw amount
+/// with `to_decimals` decimals.
+///
+/// Throws [VipersError::IntegerOverflow] if the result does not fit in a [u64], and
+/// [VipersError::PrecisionLoss] if reducing the number of decimals would truncate
+/// a non-zero remainder.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # fn main() -> Result<()> {
+/// use vipers::math::normalize_amount;
+///
+/// assert_eq!(normalize_amount(1_500_000, 6, 9)?, 1_500_000_000);
+/// assert_eq!(normalize_amount(1_500_000_000, 9, 6)?, 1_500_000);
+/// assert!(normalize_amount(1_500_000_001, 9, 6).is_err());
+/// # Ok(()) }
+/// ```
+#[track_caller]
+pub fn normalize_amount(amount: u64, from_decimals: u8, to_decimals: u8) -> Result<u64> {
+    // zero is representable at any scale, even if the factor does not fit in a u64
+    if amount == 0 {
+        return Ok(0);
+    }
+    if to_decimals >= from_decimals {
+        let factor = crate::unwrap_int!(10_u64.checked_pow((to_decimals - from_decimals).into()));
+        Ok(crate::unwrap_int!(amount.checked_mul(factor)))
+    } else {
+        let factor = match 10_u64.checked_pow((from_decimals - to_decimals).into()) {
+            Some(factor) => factor,
+            // the divisor exceeds any u64, so any non-zero amount would be truncated
+            None => crate::throw_err!(VipersError::PrecisionLoss),
+        };
+        let remainder = amount % factor;
+        crate::invariant!(
+            remainder == 0,
+            VipersError::PrecisionLoss,
+            "Rescaling would truncate the amount"
+        );
+        Ok(amount / factor)
+    }
+}
+
+/// Checked arithmetic returning [Result]s, for use where the [crate::unwrap_int] and
+/// [crate::unwrap_checked] macros are not convenient.
+///
+/// Each method is equivalent to the corresponding `checked_*` method of the integer type,
+/// but throws [VipersError::IntegerOverflow] and logs both operands on failure.
+/// Division by zero is also reported as [VipersError::IntegerOverflow].
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # fn main() -> Result<()> {
+/// use vipers::math::CheckedMathExt;
+///
+/// let fee = 1_000_u64.vsafe_mul(30)?.vsafe_div(10_000)?;
+/// assert_eq!(fee, 3);
+/// assert!(u64::MAX.vsafe_add(1).is_err());
+/// # Ok(()) }
+/// ```
+pub trait CheckedMathExt: Sized {
+    /// Checked addition.
+    fn vsafe_add(self, rhs: Self) -> Result<Self>;
+    /// Checked subtraction.
+    fn vsafe_sub(self, rhs: Self) -> Result<Self>;
+    /// Checked multiplication.
+    fn vsafe_mul(self, rhs: Self) -> Result<Self>;
+    /// Checked division.
+    fn vsafe_div(self, rhs: Self) -> Result<Self>;
+}
+
+macro_rules! impl_checked_math_ext {
+    (@method $name: ident, $checked: ident, $op: literal) => {
+        #[track_caller]
+        fn $name(self, rhs: Self) -> Result<Self> {
+            match self.$checked(rhs) {
+                Some(result) => Ok(result),
+                None => {
+                    crate::cold::fail_checked_math(self, $op, rhs);
+                    crate::throw_err!(VipersError::IntegerOverflow)
+                }
+            }
+        }
+    };
+    ($($ty: ty),+) => {
+        $(
+            impl CheckedMathExt for $ty {
+                impl_checked_math_ext!(@method vsafe_add, checked_add, "+");
+                impl_checked_math_ext!(@method vsafe_sub, checked_sub, "-");
+                impl_checked_math_ext!(@method vsafe_mul, checked_mul, "*");
+                impl_checked_math_ext!(@method vsafe_div, checked_div, "/");
+            }
+        )+
+    };
+}
+
+impl_checked_math_ext!(u64, u128, i64);
+
+/// A raw token amount with `DECIMALS` decimals.
+///
+/// Arithmetic is only defined between amounts of the same scale, so mixing amounts
+/// of mints with different decimals is a compile error. Use [ScaledAmount::rescale]
+/// to convert between scales explicitly.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # fn main() -> Result<()> {
+/// use vipers::math::ScaledAmount;
+///
+/// let usdc: ScaledAmount<6> = ScaledAmount::from_raw(1_000_000);
+/// let total = usdc.checked_add(ScaledAmount::from_raw(500_000))?;
+/// assert_eq!(total.raw(), 1_500_000);
+///
+/// let rescaled: ScaledAmount<9> = total.rescale()?;
+/// assert_eq!(rescaled.raw(), 1_500_000_000);
+/// # Ok(()) }
+/// ```
+///
+/// Amounts with different decimals cannot be added:
+///
+/// ```compile_fail
+/// use vipers::math::ScaledAmount;
+///
+/// let usdc: ScaledAmount<6> = ScaledAmount::from_raw(1_000_000);
+/// let sol: ScaledAmount<9> = ScaledAmount::from_raw(1_000_000_000);
+/// let _ = usdc.checked_add(sol);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScaledAmount<const DECIMALS: u8>(u64);
+
+impl<const DECIMALS: u8> ScaledAmount<DECIMALS> {
+    /// Number of decimals of the amount.
+    pub const DECIMALS: u8 = DECIMALS;
+
+    /// Creates a [ScaledAmount] from a raw amount with `DECIMALS` decimals.
+    pub const fn from_raw(raw: u64) -> Self {
+        Self(raw)
+    }
+
+    /// Returns the raw amount.
+    pub const fn raw(self) -> u64 {
+        self.0
+    }
+
+    /// Adds two amounts, throwing [VipersError::IntegerOverflow] on overflow.
+    #[track_caller]
+    pub fn checked_add(self, other: Self) -> Result<Self> {
+        Ok(Self(crate::unwrap_int!(self.0.checked_add(other.0))))
+    }
+
+    /// Subtracts two amounts, throwing [VipersError::IntegerOverflow] on underflow.
+    #[track_caller]
+    pub fn checked_sub(self, other: Self) -> Result<Self> {
+        Ok(Self(crate::unwrap_int!(self.0.checked_sub(other.0))))
+    }
+
+    /// Multiplies the amount by a scalar, throwing [VipersError::IntegerOverflow] on overflow.
+    #[track_caller]
+    pub fn checked_mul(self, scalar: u64) -> Result<Self> {
+        Ok(Self(crate::unwrap_int!(self.0.checked_mul(scalar))))
+    }
+
+    /// Converts the amount to `NEW_DECIMALS` decimals via [normalize_amount].
+    #[track_caller]
+    pub fn rescale<const NEW_DECIMALS: u8>(self) -> Result<ScaledAmount<NEW_DECIMALS>> {
+        Ok(ScaledAmount(normalize_amount(
+            self.0,
+            DECIMALS,
+            NEW_DECIMALS,
+        )?))
+    }
+}
+
+impl<const DECIMALS: u8> AnchorSerialize for ScaledAmount<DECIMALS> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.0.serialize(writer)
+    }
+}
+
+impl<const DECIMALS: u8> AnchorDeserialize for ScaledAmount<DECIMALS> {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        Ok(Self(AnchorDeserialize::deserialize(buf)?))
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::{assert_does_not_throw, assert_throws};
+
+    #[test]
+    fn test_checked_math_ext() {
+        assert_eq!(2_u64.vsafe_add(3).unwrap(), 5);
+        assert_eq!(5_u128.vsafe_sub(3).unwrap(), 2);
+        assert_eq!((-4_i64).vsafe_mul(3).unwrap(), -12);
+        assert_eq!(7_i64.vsafe_div(-2).unwrap(), -3);
+        assert_throws!(
+            {
+                i64::MIN.vsafe_div(-1)?;
+            },
+            VipersError::IntegerOverflow
+        );
+        assert_throws!(
+            {
+                1_u128.vsafe_div(0)?;
+            },
+            VipersError::IntegerOverflow
+        );
+
+        let ((result, line), logs) = crate::testing::capture_logs(|| (0_u64.vsafe_sub(1), line!()));
+        assert!(result.is_err());
+        assert_eq!(
+            logs,
+            vec![
+                "Program log: Integer overflow: 0 - 1".to_string(),
+                format!("Program log: Error thrown at {}:{}", file!(), line),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a: ScaledAmount<6> = ScaledAmount::from_raw(10);
+        let b: ScaledAmount<6> = ScaledAmount::from_raw(3);
+        assert_eq!(a.checked_add(b).unwrap().raw(), 13);
+        assert_eq!(a.checked_sub(b).unwrap().raw(), 7);
+        assert_eq!(a.checked_mul(4).unwrap().raw(), 40);
+        assert_throws!(
+            {
+                b.checked_sub(a)?;
+            },
+            VipersError::IntegerOverflow
+        );
+        assert_throws!(
+            {
+                ScaledAmount::<6>::from_raw(u64::MAX).checked_add(b)?;
+            },
+            VipersError::IntegerOverflow
+        );
+        assert_throws!(
+            {
+                a.checked_mul(u64::MAX)?;
+            },
+            VipersError::IntegerOverflow
+        );
+    }
+
+    #[test]
+    fn test_rescale_up() {
+        let usdc: ScaledAmount<6> = ScaledAmount::from_raw(1_234_567);
+        let rescaled: ScaledAmount<9> = usdc.rescale().unwrap();
+        assert_eq!(rescaled.raw(), 1_234_567_000);
+        assert_eq!(rescaled.rescale::<6>().unwrap(), usdc);
+        assert_eq!(usdc.rescale::<6>().unwrap(), usdc);
+        assert_throws!(
+            {
+                ScaledAmount::<6>::from_raw(u64::MAX / 100).rescale::<9>()?;
+            },
+            VipersError::IntegerOverflow
+        );
+        assert_throws!(
+            {
+                ScaledAmount::<0>::from_raw(1).rescale::<20>()?;
+            },
+            VipersError::IntegerOverflow
+        );
+    }
+
+    #[test]
+    fn test_rescale_down() {
+        let sol: ScaledAmount<9> = ScaledAmount::from_raw(1_500_000_000);
+        assert_eq!(sol.rescale::<6>().unwrap().raw(), 1_500_000);
+        assert_eq!(sol.rescale::<1>().unwrap().raw(), 15);
+        assert_throws!(
+            {
+                sol.rescale::<0>()?;
+            },
+            VipersError::PrecisionLoss
+        );
+        assert_throws!(
+            {
+                ScaledAmount::<9>::from_raw(1_500_000_001).rescale::<6>()?;
+            },
+            VipersError::PrecisionLoss
+        );
+        assert_throws!(
+            {
+                ScaledAmount::<30>::from_raw(1).rescale::<0>()?;
+            },
+            VipersError::PrecisionLoss
+        );
+        assert_does_not_throw!({
+            assert_eq!(ScaledAmount::<30>::from_raw(0).rescale::<0>()?.raw(), 0);
+        });
+    }
+
+    #[test]
+    fn test_zero_at_boundaries() {
+        assert_does_not_throw!({
+            // 10^19 is the largest power of ten which fits in a u64
+            assert_eq!(normalize_amount(0, 0, 19)?, 0);
+            assert_eq!(normalize_amount(0, 0, 20)?, 0);
+            assert_eq!(normalize_amount(0, 0, u8::MAX)?, 0);
+            assert_eq!(normalize_amount(0, 19, 0)?, 0);
+            assert_eq!(normalize_amount(0, 20, 0)?, 0);
+            assert_eq!(normalize_amount(0, u8::MAX, 0)?, 0);
+            assert_eq!(ScaledAmount::<0>::from_raw(0).rescale::<20>()?.raw(), 0);
+            assert_eq!(ScaledAmount::<20>::from_raw(0).rescale::<0>()?.raw(), 0);
+            assert_eq!(normalize_amount(1, 0, 19)?, 10_u64.pow(19));
+        });
+        assert_throws!(
+            {
+                normalize_amount(1, 0, 20)?;
+            },
+            VipersError::IntegerOverflow
+        );
+        assert_throws!(
+            {
+                normalize_amount(1, 20, 0)?;
+            },
+            VipersError::PrecisionLoss
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let amount: ScaledAmount<6> = ScaledAmount::from_raw(1_234_567);
+        let bytes = amount.try_to_vec().unwrap();
+        assert_eq!(bytes, 1_234_567_u64.to_le_bytes());
+        assert_eq!(ScaledAmount::<6>::try_from_slice(&bytes).unwrap(), amount);
+    }
+}
@@ -0,0 +1,282 @@
+//! Pausing of individual instructions.
+//!
+//! Programs store [PauseFlags] in a config account, with one bit per pausable operation.
+//! [crate::assert_not_paused] checks a single operation, and [crate::pausable_handlers]
+//! declares the pause coverage of every handler in a single table.
+
+use anchor_lang::prelude::*;
+use std::io::Write;
+
+/// A bitmask of paused operations.
+///
+/// The meaning of each bit is defined by the program, e.g. `const DEPOSIT: u64 = 1 << 0;`.
+///
+/// # Example
+///
+/// ```
+/// use vipers::pause::PauseFlags;
+///
+/// const DEPOSIT: u64 = 1 << 0;
+/// const WITHDRAW: u64 = 1 << 1;
+///
+/// let mut flags = PauseFlags::default();
+/// flags.pause(DEPOSIT);
+/// assert!(flags.is_paused(DEPOSIT));
+/// assert!(!flags.is_paused(WITHDRAW));
+/// flags.unpause(DEPOSIT);
+/// assert_eq!(flags, PauseFlags::NONE);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PauseFlags {
+    bits: u64,
+}
+
+impl PauseFlags {
+    /// No operations are paused.
+    pub const NONE: PauseFlags = PauseFlags { bits: 0 };
+    /// All operations are paused.
+    pub const ALL: PauseFlags = PauseFlags { bits: u64::MAX };
+
+    /// Creates [PauseFlags] from the raw bitmask.
+    pub const fn from_bits(bits: u64) -> Self {
+        Self { bits }
+    }
+
+    /// Returns the raw bitmask.
+    pub const fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Returns true if any of the bits of `flag` are paused.
+    pub const fn is_paused(&self, flag: u64) -> bool {
+        self.bits & flag != 0
+    }
+
+    /// Pauses the bits of `flag`.
+    pub fn pause(&mut self, flag: u64) {
+        self.bits |= flag;
+    }
+
+    /// Unpauses the bits of `flag`.
+    pub fn unpause(&mut self, flag: u64) {
+        self.bits &= !flag;
+    }
+}
+
+impl AnchorSerialize for PauseFlags {
+    fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.bits.serialize(writer)
+    }
+}
+
+impl AnchorDeserialize for PauseFlags {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        Ok(Self::from_bits(AnchorDeserialize::deserialize(buf)?))
+    }
+}
+
+/// Asserts that the given operation is not paused,
+/// throwing [crate::VipersError::ProtocolPaused] otherwise.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// # use vipers::pause::PauseFlags;
+/// # fn main() {
+/// const DEPOSIT: u64 = 1 << 0;
+/// const WITHDRAW: u64 = 1 << 1;
+/// let flags = PauseFlags::from_bits(DEPOSIT);
+/// assert_throws!({
+///   assert_not_paused!(flags, DEPOSIT);
+/// }, vipers::VipersError::ProtocolPaused);
+/// assert_does_not_throw!({
+///   assert_not_paused!(flags, WITHDRAW);
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_not_paused {
+    ($pause_flags: expr, $flag: expr $(,)?) => {{
+        let __flag: u64 = $flag;
+        if ($pause_flags).is_paused(__flag) {
+            msg!("Paused: {}", stringify!($flag));
+            $crate::throw_err!($crate::VipersError::ProtocolPaused);
+        }
+    }};
+}
+
+/// Generates wrappers for instruction handlers which run [crate::assert_not_paused] first.
+///
+/// Each entry restates the signature of a handler, followed by the path to the handler and
+/// its pause flag. The generated wrapper has the same signature: it loads the [PauseFlags]
+/// from the context, checks the flag, and then calls the handler.
+///
+/// The [PauseFlags] are read from `ctx.accounts.config.pause_flags` by default. Another
+/// field path may be given via a leading `pause_flags = state.flags;`.
+///
+/// Anchor's `#[program]` module only accepts function items, so its handlers should
+/// call the generated wrappers. A misnamed handler or missing config field fails to
+/// compile at the wrapper, pointing at the entry in the table.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// # use vipers::IntoCmpError;
+/// use vipers::pause::PauseFlags;
+///
+/// pub const DEPOSIT: u64 = 1 << 0;
+/// pub const WITHDRAW: u64 = 1 << 1;
+///
+/// pub struct Config {
+///     pub pause_flags: PauseFlags,
+/// }
+/// pub struct Deposit {
+///     pub config: Config,
+/// }
+/// pub struct Withdraw {
+///     pub config: Config,
+/// }
+///
+/// mod handlers {
+///     # use super::*;
+///     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+///         Ok(())
+///     }
+///     pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// pausable_handlers! {
+///     pause_flags = config.pause_flags;
+///
+///     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()>
+///         => handlers::deposit, DEPOSIT;
+///     pub fn withdraw(ctx: Context<Withdraw>) -> Result<()>
+///         => handlers::withdraw, WITHDRAW;
+/// }
+///
+/// # fn main() {
+/// let config = Config { pause_flags: PauseFlags::from_bits(DEPOSIT) };
+/// let accounts = &mut Deposit { config };
+/// let ctx = Context {
+///     program_id: &vipers::ID,
+///     accounts,
+///     remaining_accounts: &[],
+///     bumps: Default::default(),
+/// };
+/// assert_eq!(
+///     deposit(ctx, 1).into_cmp_error(),
+///     error!(vipers::VipersError::ProtocolPaused).into_cmp_error()
+/// );
+/// # }
+/// ```
+///
+/// A misnamed handler fails to compile:
+///
+/// ```compile_fail
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// # use vipers::pause::PauseFlags;
+/// # pub const DEPOSIT: u64 = 1 << 0;
+/// # pub struct Config { pub pause_flags: PauseFlags }
+/// # pub struct Deposit { pub config: Config }
+/// mod handlers {
+///     # use super::*;
+///     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// pausable_handlers! {
+///     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()>
+///         => handlers::depositt, DEPOSIT;
+/// }
+/// # fn main() {}
+/// ```
+///
+/// As does a missing config field:
+///
+/// ```compile_fail
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// # use vipers::pause::PauseFlags;
+/// # pub const DEPOSIT: u64 = 1 << 0;
+/// # pub struct Config { pub pause_flags: PauseFlags }
+/// pub struct Deposit {
+///     pub state: Config,
+/// }
+/// # mod handlers {
+/// #     use super::*;
+/// #     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+/// #         Ok(())
+/// #     }
+/// # }
+///
+/// pausable_handlers! {
+///     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()>
+///         => handlers::deposit, DEPOSIT;
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! pausable_handlers {
+    (@impl [$($field: ident).+]) => {};
+    (@impl [$($field: ident).+]
+        $(#[$attr: meta])*
+        $vis: vis fn $name: ident(
+            $ctx: ident: $ctx_ty: ty $(, $arg: ident: $arg_ty: ty)* $(,)?
+        ) -> $ret: ty => $handler: path, $flag: expr;
+        $($rest: tt)*
+    ) => {
+        $(#[$attr])*
+        $vis fn $name($ctx: $ctx_ty $(, $arg: $arg_ty)*) -> $ret {
+            $crate::assert_not_paused!($ctx.accounts.$($field).+, $flag);
+            $handler($ctx $(, $arg)*)
+        }
+        $crate::pausable_handlers!(@impl [$($field).+] $($rest)*);
+    };
+    (@impl $($rest: tt)*) => {
+        compile_error!(
+            "Invalid pausable_handlers! entry: expected `fn name(ctx: Context<Accounts>, args...) -> Result<()> => path::to::handler, FLAG;`"
+        );
+    };
+    (pause_flags = $($field: ident).+; $($rest: tt)*) => {
+        $crate::pausable_handlers!(@impl [$($field).+] $($rest)*);
+    };
+    ($($rest: tt)*) => {
+        $crate::pausable_handlers!(@impl [config.pause_flags] $($rest)*);
+    };
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_flags() {
+        let mut flags = PauseFlags::NONE;
+        assert!(!flags.is_paused(1));
+        flags.pause(1 | 4);
+        assert_eq!(flags.bits(), 5);
+        assert!(flags.is_paused(1));
+        assert!(!flags.is_paused(2));
+        assert!(flags.is_paused(2 | 4));
+        flags.unpause(1);
+        assert_eq!(flags, PauseFlags::from_bits(4));
+        assert!(PauseFlags::ALL.is_paused(1 << 63));
+    }
+
+    #[test]
+    fn test_serialization() {
+        let flags = PauseFlags::from_bits(0x0102);
+        let bytes = flags.try_to_vec().unwrap();
+        assert_eq!(bytes, 0x0102_u64.to_le_bytes());
+        assert_eq!(PauseFlags::try_from_slice(&bytes).unwrap(), flags);
+    }
+}
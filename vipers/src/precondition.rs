@@ -0,0 +1,179 @@
+//! Reusable preconditions on accounts and other program state.
+//!
+//! A [Precondition] captures a composite check, such as an account being in good standing,
+//! which many instructions must run. [crate::assert_precondition] runs the check and returns
+//! [Checked] evidence, which handlers can require as an argument so that the check cannot
+//! be forgotten.
+
+use anchor_lang::prelude::*;
+use std::ops::Deref;
+
+/// A check which must pass before a value may be used.
+pub trait Precondition {
+    /// Checks the precondition, throwing if it does not hold.
+    fn check(&self) -> Result<()>;
+}
+
+/// Evidence that the [Precondition] of the wrapped value has passed.
+///
+/// This can only be constructed via [Checked::new] or [crate::assert_precondition],
+/// both of which run the check first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checked<T>(T);
+
+impl<'a, T: Precondition + ?Sized> Checked<&'a T> {
+    /// Checks the [Precondition] of `value`, returning evidence that it passed.
+    ///
+    /// On failure, the type of the value is logged and the error is returned unchanged.
+    #[track_caller]
+    pub fn new(value: &'a T) -> Result<Self> {
+        if let Err(err) = value.check() {
+            msg!("Precondition failed: {}", std::any::type_name::<T>());
+            crate::log_code_location!();
+            return Err(err);
+        }
+        Ok(Checked(value))
+    }
+}
+
+impl<T> Checked<T> {
+    /// Returns the checked value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Deref for Checked<&T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0
+    }
+}
+
+/// Checks the [Precondition] of a value, evaluating to [Checked] evidence that it passed.
+///
+/// The value is borrowed, so to check the data of an `Account<'info, T>`, dereference it:
+/// `assert_precondition!(*ctx.accounts.obligation)`.
+///
+/// On failure, the expression and its type are logged, and the error thrown
+/// by [Precondition::check] is returned unchanged.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// use vipers::precondition::{Checked, Precondition};
+///
+/// #[error_code]
+/// pub enum ErrorCode { ObligationStale }
+///
+/// pub struct Obligation {
+///     pub last_update_slot: u64,
+/// }
+///
+/// impl Precondition for Obligation {
+///     fn check(&self) -> Result<()> {
+///         invariant!(self.last_update_slot >= 10, ObligationStale);
+///         Ok(())
+///     }
+/// }
+///
+/// fn borrow(obligation: Checked<&Obligation>) -> Result<u64> {
+///     Ok(obligation.last_update_slot)
+/// }
+///
+/// # fn main() -> Result<()> {
+/// let obligation = Obligation { last_update_slot: 12 };
+/// let checked = assert_precondition!(obligation);
+/// assert_eq!(borrow(checked)?, 12);
+///
+/// let stale = Obligation { last_update_slot: 5 };
+/// assert_throws!({
+///     assert_precondition!(stale);
+/// }, ErrorCode::ObligationStale);
+/// # Ok(()) }
+/// ```
+#[macro_export]
+macro_rules! assert_precondition {
+    ($value: expr $(,)?) => {
+        $crate::precondition::Checked::new(&$value).map_err(|__err| {
+            msg!("Precondition failed: {}", stringify!($value));
+            __err
+        })?
+    };
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::testing::capture_logs;
+    use crate::{assert_throws, IntoCmpError};
+
+    #[error_code]
+    pub enum ErrorCode {
+        Liquidatable,
+        Frozen,
+    }
+
+    #[derive(Default)]
+    struct Obligation {
+        health: u64,
+        frozen: bool,
+    }
+
+    impl Precondition for Obligation {
+        fn check(&self) -> Result<()> {
+            crate::invariant!(self.health >= 100, ErrorCode::Liquidatable);
+            crate::invariant!(!self.frozen, ErrorCode::Frozen);
+            Ok(())
+        }
+    }
+
+    fn health(obligation: Checked<&Obligation>) -> u64 {
+        obligation.health
+    }
+
+    #[test]
+    fn test_precondition_pass() {
+        let obligation = Obligation {
+            health: 150,
+            ..Default::default()
+        };
+        let checked = Checked::new(&obligation).unwrap();
+        assert_eq!(health(checked), 150);
+        assert!(std::ptr::eq(checked.into_inner(), &obligation));
+    }
+
+    #[test]
+    fn test_precondition_fail() {
+        let liquidatable = Obligation::default();
+        assert!(Checked::new(&liquidatable).is_err());
+        assert_throws!(
+            {
+                assert_precondition!(liquidatable);
+            },
+            ErrorCode::Liquidatable
+        );
+
+        let frozen = Obligation {
+            health: 150,
+            frozen: true,
+        };
+        let (result, logs) = capture_logs(|| -> Result<u64> {
+            let checked = assert_precondition!(frozen);
+            Ok(health(checked))
+        });
+        assert_eq!(
+            result.into_cmp_error(),
+            error!(ErrorCode::Frozen).into_cmp_error()
+        );
+        assert!(logs.contains(&format!(
+            "Program log: Precondition failed: {}",
+            std::any::type_name::<Obligation>()
+        )));
+        assert!(logs.contains(&"Program log: Precondition failed: frozen".to_string()));
+    }
+}
@@ -0,0 +1,18 @@
+//! Program IDs of commonly used programs.
+
+pub use anchor_lang::solana_program::system_program as system;
+
+pub mod token {
+    //! The SPL Token program.
+    anchor_lang::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+}
+
+pub mod token_2022 {
+    //! The SPL Token 2022 program.
+    anchor_lang::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+}
+
+pub mod associated_token {
+    //! The SPL Associated Token Account program.
+    anchor_lang::declare_id!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+}
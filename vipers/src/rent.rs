@@ -0,0 +1,246 @@
+//! Helpers for loading the [Rent] sysvar and checking rent exemption.
+//!
+//! Newer runtimes expose [Rent] via [Sysvar::get], but older clients may still pass the
+//! rent sysvar account. [crate::load_rent] and [crate::assert_rent_exempt] accept either,
+//! so programs can migrate client-by-client without changing their checks:
+//!
+//! - no argument: [Rent::get]
+//! - `rent = <&Rent>`: an already-loaded [Rent]
+//! - `rent_sysvar = <account>`: an account claimed to be the rent sysvar, loaded via [rent_from_sysvar]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+
+/// Loads the [Rent] from an account claimed to be the rent sysvar.
+///
+/// The key of the account is checked before its data is deserialized, so a spoofed
+/// account with attacker-chosen rent parameters throws [crate::VipersError::InvalidSysvar].
+#[track_caller]
+pub fn rent_from_sysvar(rent_sysvar: &AccountInfo) -> Result<Rent> {
+    crate::assert_keys_eq!(
+        *rent_sysvar,
+        sysvar::rent::ID,
+        crate::VipersError::InvalidSysvar,
+        "rent sysvar"
+    );
+    Ok(Rent::from_account_info(rent_sysvar)?)
+}
+
+/// Loads the [Rent], either from the runtime, an existing `&Rent`, or the rent sysvar account.
+///
+/// This returns early with an error if the [Rent] could not be loaded.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # use vipers::{IntoCmpError, VipersError};
+/// # #[macro_use] extern crate vipers;
+/// # fn main() -> Result<()> {
+/// let rent = Rent::default();
+/// let loaded = load_rent!(rent = &rent);
+/// assert_eq!(loaded, rent);
+///
+/// let mut lamports = 0;
+/// let mut data = [];
+/// let key = Pubkey::new_unique();
+/// let spoofed = AccountInfo::new(
+///     &key, false, false, &mut lamports, &mut data, &key, false, 0,
+/// );
+/// let result = (|| -> Result<()> {
+///     load_rent!(rent_sysvar = spoofed);
+///     Ok(())
+/// })();
+/// assert_eq!(result.into_cmp_error(), error!(VipersError::InvalidSysvar).into_cmp_error());
+/// # Ok(()) }
+/// ```
+#[macro_export]
+macro_rules! load_rent {
+    () => {
+        <::anchor_lang::prelude::Rent as ::anchor_lang::solana_program::sysvar::Sysvar>::get()?
+    };
+    (rent = $rent: expr $(,)?) => {{
+        let __rent: &::anchor_lang::prelude::Rent = $rent;
+        *__rent
+    }};
+    (rent_sysvar = $rent_sysvar: expr $(,)?) => {
+        $crate::rent::rent_from_sysvar(&::anchor_lang::ToAccountInfo::to_account_info(
+            &$rent_sysvar,
+        ))?
+    };
+}
+
+/// Asserts that an account holds enough lamports to be rent-exempt,
+/// throwing [crate::VipersError::NotRentExempt] otherwise.
+///
+/// The [Rent] is loaded via [crate::load_rent]:
+///
+/// ```ignore
+/// assert_rent_exempt!(account);
+/// assert_rent_exempt!(account, rent = &rent);
+/// assert_rent_exempt!(account, rent_sysvar = ctx.accounts.rent);
+/// ```
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// # fn main() -> Result<()> {
+/// let rent = Rent::default();
+/// let mut lamports = rent.minimum_balance(8);
+/// let mut data = [0_u8; 8];
+/// let key = Pubkey::new_unique();
+/// let account = AccountInfo::new(
+///     &key, false, true, &mut lamports, &mut data, &key, false, 0,
+/// );
+/// assert_rent_exempt!(account, rent = &rent);
+/// # Ok(()) }
+/// ```
+#[macro_export]
+macro_rules! assert_rent_exempt {
+    (@check $account: expr, $rent: expr) => {{
+        let __rent: ::anchor_lang::prelude::Rent = $rent;
+        let __account = ::anchor_lang::ToAccountInfo::to_account_info(&$account);
+        let __lamports = __account.lamports();
+        let __data_len = __account.data_len();
+        if !__rent.is_exempt(__lamports, __data_len) {
+            msg!(
+                "Account {} is not rent-exempt: has {} lamports, needs {}",
+                stringify!($account),
+                __lamports,
+                __rent.minimum_balance(__data_len)
+            );
+            $crate::throw_err!($crate::VipersError::NotRentExempt);
+        }
+    }};
+    ($account: expr, rent = $rent: expr $(,)?) => {
+        $crate::assert_rent_exempt!(@check $account, $crate::load_rent!(rent = $rent))
+    };
+    ($account: expr, rent_sysvar = $rent_sysvar: expr $(,)?) => {
+        $crate::assert_rent_exempt!(
+            @check $account,
+            $crate::load_rent!(rent_sysvar = $rent_sysvar)
+        )
+    };
+    ($account: expr $(,)?) => {
+        $crate::assert_rent_exempt!(@check $account, $crate::load_rent!())
+    };
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::{assert_does_not_throw, assert_throws, VipersError};
+
+    /// Serializes `rent` into a buffer suitable for a rent sysvar account.
+    fn rent_data(rent: &Rent) -> Vec<u8> {
+        let mut data = vec![0_u8; Rent::size_of()];
+        let key = sysvar::rent::ID;
+        let mut lamports = 0;
+        let mut info =
+            AccountInfo::new(&key, false, false, &mut lamports, &mut data, &key, false, 0);
+        rent.to_account_info(&mut info).unwrap();
+        drop(info);
+        data
+    }
+
+    #[test]
+    fn test_explicit_rent() {
+        let rent = Rent::default();
+        let key = Pubkey::new_unique();
+        let mut data = [0_u8; 100];
+        let mut lamports = rent.minimum_balance(data.len());
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false, 0);
+        assert_does_not_throw!({
+            assert_rent_exempt!(account, rent = &rent);
+        });
+
+        **account.lamports.borrow_mut() -= 1;
+        assert_throws!(
+            {
+                assert_rent_exempt!(account, rent = &rent);
+            },
+            VipersError::NotRentExempt
+        );
+    }
+
+    #[test]
+    fn test_runtime_rent() {
+        let rent = Rent::default();
+        let key = Pubkey::new_unique();
+        let mut data = [0_u8; 100];
+        let mut lamports = rent.minimum_balance(data.len()) - 1;
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false, 0);
+        crate::testing::install_rent_stub();
+        assert_throws!(
+            {
+                assert_eq!(load_rent!(), rent);
+                assert_rent_exempt!(account);
+            },
+            VipersError::NotRentExempt
+        );
+    }
+
+    #[test]
+    fn test_rent_sysvar() {
+        let rent = Rent::default();
+        let sysvar_key = sysvar::rent::ID;
+        let mut sysvar_data = rent_data(&rent);
+        let mut sysvar_lamports = 1;
+        let rent_sysvar = AccountInfo::new(
+            &sysvar_key,
+            false,
+            false,
+            &mut sysvar_lamports,
+            &mut sysvar_data,
+            &sysvar::ID,
+            false,
+            0,
+        );
+        assert_eq!(rent_from_sysvar(&rent_sysvar).unwrap(), rent);
+
+        let key = Pubkey::new_unique();
+        let mut data = [0_u8; 100];
+        let mut lamports = rent.minimum_balance(data.len());
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false, 0);
+        assert_does_not_throw!({
+            assert_rent_exempt!(account, rent_sysvar = rent_sysvar);
+        });
+    }
+
+    #[test]
+    fn test_spoofed_rent_sysvar() {
+        // rent parameters under which every account is exempt
+        let spoofed_rent = Rent {
+            lamports_per_byte_year: 0,
+            exemption_threshold: 0.0,
+            burn_percent: 0,
+        };
+        let spoofed_key = Pubkey::new_unique();
+        let mut spoofed_data = rent_data(&spoofed_rent);
+        let mut spoofed_lamports = 1;
+        let spoofed_sysvar = AccountInfo::new(
+            &spoofed_key,
+            false,
+            false,
+            &mut spoofed_lamports,
+            &mut spoofed_data,
+            &sysvar::ID,
+            false,
+            0,
+        );
+
+        let key = Pubkey::new_unique();
+        let mut data = [0_u8; 100];
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false, 0);
+        assert_throws!(
+            {
+                assert_rent_exempt!(account, rent_sysvar = spoofed_sysvar);
+            },
+            VipersError::InvalidSysvar
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Allocation-free construction of PDA seeds.
+//!
+//! [seeds] and [signer_seeds] build fixed-size arrays whose length is inferred from
+//! the number of macro arguments, so seeds live on the caller's stack rather than
+//! in a heap-allocated [Vec].
+
+/// Maximum number of seeds a program derived address may have.
+///
+/// Passing more than this many seeds to [seeds] or [signer_seeds] is a compile error.
+pub const MAX_SEEDS: usize = anchor_lang::solana_program::pubkey::MAX_SEEDS;
+
+/// Builds a `[&[u8]; N]` array of PDA seeds, where `N` is the number of seeds provided.
+///
+/// Each seed must be indexable by `[..]`, i.e. a byte string, byte array, or byte slice.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() {
+/// let user = Pubkey::new_unique();
+/// let bump: u8 = 254;
+/// let seeds: [&[u8]; 3] = seeds!(b"vault", user.as_ref(), [bump]);
+/// assert_eq!(seeds[2], &[254]);
+/// # }
+/// ```
+///
+/// More than [MAX_SEEDS] seeds fails to compile:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate vipers; fn main() {
+/// let _seeds = seeds!(
+///     b"0", b"1", b"2", b"3", b"4", b"5", b"6", b"7", b"8",
+///     b"9", b"10", b"11", b"12", b"13", b"14", b"15", b"16"
+/// );
+/// # }
+/// ```
+#[macro_export]
+macro_rules! seeds {
+    (
+        $_0: expr, $_1: expr, $_2: expr, $_3: expr, $_4: expr, $_5: expr, $_6: expr, $_7: expr,
+        $_8: expr, $_9: expr, $_10: expr, $_11: expr, $_12: expr, $_13: expr, $_14: expr,
+        $_15: expr, $_16: expr $(, $rest: expr)* $(,)?
+    ) => {
+        compile_error!("Too many seeds: a program derived address may have at most 16 seeds (vipers::seeds::MAX_SEEDS).")
+    };
+    ($($seed: expr),+ $(,)?) => {
+        [$(&$seed[..]),+]
+    };
+}
+
+/// Builds the signer seeds for `invoke_signed`, a `[&[&[u8]]; 1]` array
+/// wrapping the seeds of a single PDA.
+///
+/// Temporaries such as `[bump]` are not lifetime-extended through the nested array,
+/// so bind them to a local before passing them in.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() {
+/// let user = Pubkey::new_unique();
+/// let bump = [254_u8];
+/// let signer_seeds: [&[&[u8]]; 1] = signer_seeds!(b"vault", user.as_ref(), bump);
+/// let _signer: &[&[&[u8]]] = &signer_seeds;
+/// assert_eq!(signer_seeds[0].len(), 3);
+/// # }
+/// ```
+///
+/// More than [MAX_SEEDS] seeds fails to compile:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate vipers; fn main() {
+/// let _seeds = signer_seeds!(
+///     b"0", b"1", b"2", b"3", b"4", b"5", b"6", b"7", b"8",
+///     b"9", b"10", b"11", b"12", b"13", b"14", b"15", b"16"
+/// );
+/// # }
+/// ```
+#[macro_export]
+macro_rules! signer_seeds {
+    ($($seed: expr),+ $(,)?) => {
+        [&$crate::seeds!($($seed),+)[..]]
+    };
+}
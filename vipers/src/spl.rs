@@ -0,0 +1,799 @@
+//! Checked helpers for SPL Token instructions.
+//!
+//! The `check_*` functions validate the preconditions of each instruction. With the
+//! `token-cpi` feature enabled, the `checked_*` functions run these checks and then
+//! perform the corresponding CPI.
+
+use crate::AsKeyRef;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::spl_token::state::{Account as SplTokenAccount, Mint as SplMint};
+
+/// Checks the preconditions of a token transfer:
+/// - `token_program` is the SPL Token program
+/// - `source` is owned by `authority`
+/// - `source` holds at least `amount` tokens
+#[track_caller]
+pub fn check_transfer<P: AsKeyRef + ?Sized, A: AsKeyRef + ?Sized>(
+    token_program: &P,
+    source: &SplTokenAccount,
+    authority: &A,
+    amount: u64,
+) -> Result<()> {
+    crate::assert_keys_eq!(
+        *token_program,
+        anchor_spl::token::ID,
+        crate::VipersError::ProgramIDMismatch,
+        "token program"
+    );
+    crate::assert_keys_eq!(source.owner, *authority, "source owner");
+    crate::invariant!(
+        source.amount >= amount,
+        crate::VipersError::InsufficientBalance,
+        "source balance"
+    );
+    Ok(())
+}
+
+/// Checks the preconditions of a token mint:
+/// - `token_program` is the SPL Token program
+/// - the mint authority of `mint` is `authority`
+/// - `destination` is a token account of `mint`
+#[track_caller]
+pub fn check_mint_to<P: AsKeyRef + ?Sized, M: AsKeyRef + ?Sized, A: AsKeyRef + ?Sized>(
+    token_program: &P,
+    mint_key: &M,
+    mint: &SplMint,
+    destination: &SplTokenAccount,
+    authority: &A,
+) -> Result<()> {
+    crate::assert_keys_eq!(
+        *token_program,
+        anchor_spl::token::ID,
+        crate::VipersError::ProgramIDMismatch,
+        "token program"
+    );
+    crate::invariant!(
+        mint.mint_authority == COption::Some(*authority.as_key_ref()),
+        crate::VipersError::KeyMismatch,
+        "mint authority"
+    );
+    crate::assert_keys_eq!(destination.mint, *mint_key, "destination mint");
+    Ok(())
+}
+
+/// Checks the preconditions of a token burn:
+/// - `token_program` is the SPL Token program
+/// - `source` is a token account of `mint`, owned by `authority`
+/// - `source` holds at least `amount` tokens
+#[track_caller]
+pub fn check_burn<P: AsKeyRef + ?Sized, M: AsKeyRef + ?Sized, A: AsKeyRef + ?Sized>(
+    token_program: &P,
+    mint_key: &M,
+    source: &SplTokenAccount,
+    authority: &A,
+    amount: u64,
+) -> Result<()> {
+    crate::assert_keys_eq!(
+        *token_program,
+        anchor_spl::token::ID,
+        crate::VipersError::ProgramIDMismatch,
+        "token program"
+    );
+    crate::assert_keys_eq!(source.mint, *mint_key, "source mint");
+    crate::assert_keys_eq!(source.owner, *authority, "source owner");
+    crate::invariant!(
+        source.amount >= amount,
+        crate::VipersError::InsufficientBalance,
+        "source balance"
+    );
+    Ok(())
+}
+
+/// The token program supplied to an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenProgramKind {
+    /// The original SPL Token program.
+    Legacy,
+    /// The SPL Token 2022 program.
+    Token2022,
+}
+
+impl TokenProgramKind {
+    /// Returns the program ID of the token program.
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            TokenProgramKind::Legacy => crate::program_ids::token::ID,
+            TokenProgramKind::Token2022 => crate::program_ids::token_2022::ID,
+        }
+    }
+}
+
+/// Identifies which token program the given key is, throwing
+/// [crate::VipersError::ProgramIDMismatch] if it is neither SPL Token nor Token 2022.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # fn main() -> Result<()> {
+/// use vipers::spl::{identify_token_program, TokenProgramKind};
+///
+/// let token_program = vipers::program_ids::token_2022::ID;
+/// assert_eq!(identify_token_program(&token_program)?, TokenProgramKind::Token2022);
+/// assert!(identify_token_program(&Pubkey::new_unique()).is_err());
+/// # Ok(()) }
+/// ```
+#[track_caller]
+pub fn identify_token_program<P: AsKeyRef + ?Sized>(token_program: &P) -> Result<TokenProgramKind> {
+    let key = token_program.as_key_ref();
+    if crate::keys_eq(key, &crate::program_ids::token::ID) {
+        Ok(TokenProgramKind::Legacy)
+    } else if crate::keys_eq(key, &crate::program_ids::token_2022::ID) {
+        Ok(TokenProgramKind::Token2022)
+    } else {
+        msg!("Not a token program: {}", key);
+        crate::throw_err!(crate::VipersError::ProgramIDMismatch)
+    }
+}
+
+/// Asserts that the given account is either the SPL Token or Token 2022 program,
+/// throwing [crate::VipersError::ProgramIDMismatch] otherwise.
+///
+/// Use [identify_token_program] to find out which one it is.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// # fn main() -> Result<()> {
+/// let token_program = vipers::program_ids::token::ID;
+/// assert_token_program!(token_program);
+/// # Ok(()) }
+/// ```
+#[macro_export]
+macro_rules! assert_token_program {
+    ($token_program: expr $(,)?) => {
+        $crate::spl::identify_token_program(&$token_program)?;
+    };
+}
+
+/// Derives the associated token account of `owner` for `mint` under the given token program,
+/// returning the address and its bump.
+///
+/// This generates a PDA, which uses a lot of compute units. Programs should store the
+/// result and check it via [crate::assert_cached_ata].
+pub fn derive_ata_with_program(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &crate::program_ids::associated_token::ID,
+    )
+}
+
+/// Derives the SPL Token associated token account of `owner` for `mint`,
+/// returning the address and its bump.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// use vipers::spl::derive_ata;
+///
+/// let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+/// let (ata, bump) = derive_ata(&owner, &mint);
+/// assert_eq!(
+///     Pubkey::create_program_address(
+///         &[owner.as_ref(), vipers::program_ids::token::ID.as_ref(), mint.as_ref(), &[bump]],
+///         &vipers::program_ids::associated_token::ID,
+///     ),
+///     Ok(ata)
+/// );
+/// ```
+pub fn derive_ata<O: AsKeyRef + ?Sized, M: AsKeyRef + ?Sized>(owner: &O, mint: &M) -> (Pubkey, u8) {
+    derive_ata_with_program(
+        owner.as_key_ref(),
+        mint.as_key_ref(),
+        &crate::program_ids::token::ID,
+    )
+}
+
+/// Derives the Token 2022 associated token account of `owner` for `mint`,
+/// returning the address and its bump.
+pub fn derive_ata_2022<O: AsKeyRef + ?Sized, M: AsKeyRef + ?Sized>(
+    owner: &O,
+    mint: &M,
+) -> (Pubkey, u8) {
+    derive_ata_with_program(
+        owner.as_key_ref(),
+        mint.as_key_ref(),
+        &crate::program_ids::token_2022::ID,
+    )
+}
+
+/// Checks that `token_account` is the associated token account of `owner` for `mint`,
+/// throwing [crate::VipersError::ATAMismatch] otherwise.
+///
+/// If `cached_ata` is set, the token account is only compared against it. Otherwise,
+/// the ATA is derived once, and a suggestion to cache it is logged.
+///
+/// This is the check performed by [crate::assert_cached_ata].
+#[track_caller]
+pub fn check_cached_ata<A: AsKeyRef + ?Sized, O: AsKeyRef + ?Sized, M: AsKeyRef + ?Sized>(
+    token_account: &A,
+    cached_ata: &Pubkey,
+    owner: &O,
+    mint: &M,
+    token_program: &Pubkey,
+) -> Result<()> {
+    let token_account = token_account.as_key_ref();
+    if *cached_ata != Pubkey::default() {
+        if !crate::keys_eq(token_account, cached_ata) {
+            msg!(
+                "ATA mismatch: {} (token account) != {} (cached)",
+                token_account,
+                cached_ata
+            );
+            crate::throw_err!(crate::VipersError::ATAMismatch);
+        }
+        return Ok(());
+    }
+
+    let (ata, bump) = derive_ata_with_program(owner.as_key_ref(), mint.as_key_ref(), token_program);
+    if !crate::keys_eq(token_account, &ata) {
+        msg!(
+            "ATA mismatch: {} (token account) != {} (derived)",
+            token_account,
+            ata
+        );
+        msg!("Owner: {}", owner.as_key_ref());
+        msg!("Mint: {}", mint.as_key_ref());
+        crate::throw_err!(crate::VipersError::ATAMismatch);
+    }
+    msg!(
+        "ATA is not cached: consider caching {} (bump {})",
+        ata,
+        bump
+    );
+    Ok(())
+}
+
+/// Asserts that a token account is the associated token account of `owner` for `mint`,
+/// comparing against a cached ATA key where possible.
+///
+/// If the cached key is [Pubkey::default], the ATA is derived instead, which uses a lot of
+/// compute units. A token program may be given via `token_program = key`; it defaults to
+/// SPL Token. See [check_cached_ata].
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers;
+/// # fn main() -> Result<()> {
+/// use vipers::spl::derive_ata;
+///
+/// let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+/// let (ata, _) = derive_ata(&owner, &mint);
+///
+/// // derives the ATA, since nothing is cached yet
+/// assert_cached_ata!(ata, Pubkey::default(), owner, mint);
+/// // only compares keys
+/// let cached_ata = ata;
+/// assert_cached_ata!(ata, cached_ata, owner, mint);
+///
+/// let (ata_2022, _) = vipers::spl::derive_ata_2022(&owner, &mint);
+/// assert_cached_ata!(
+///     ata_2022,
+///     Pubkey::default(),
+///     owner,
+///     mint,
+///     token_program = vipers::program_ids::token_2022::ID
+/// );
+/// # Ok(()) }
+/// ```
+#[macro_export]
+macro_rules! assert_cached_ata {
+    ($token_account: expr, $cached_ata: expr, $owner: expr, $mint: expr $(,)?) => {
+        $crate::assert_cached_ata!(
+            $token_account,
+            $cached_ata,
+            $owner,
+            $mint,
+            token_program = $crate::program_ids::token::ID
+        )
+    };
+    (
+        $token_account: expr,
+        $cached_ata: expr,
+        $owner: expr,
+        $mint: expr,
+        token_program = $token_program: expr $(,)?
+    ) => {
+        $crate::spl::check_cached_ata(
+            &$token_account,
+            &$cached_ata,
+            &$owner,
+            &$mint,
+            $crate::AsKeyRef::as_key_ref(&$token_program),
+        )?;
+    };
+}
+
+/// Checks that a token balance changed by exactly `expected_delta`, throwing
+/// [crate::VipersError::UnexpectedBalanceChange] otherwise.
+///
+/// This is the check performed by [crate::assert_balance_change] after reloading
+/// the token account.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # fn main() -> Result<()> {
+/// use vipers::spl::check_balance_change;
+///
+/// check_balance_change(1_000, 1_500, 500)?;
+/// check_balance_change(1_000, 400, -600)?;
+/// assert!(check_balance_change(1_000, 1_499, 500).is_err());
+/// # Ok(()) }
+/// ```
+#[track_caller]
+pub fn check_balance_change(
+    previous_amount: u64,
+    current_amount: u64,
+    expected_delta: i128,
+) -> Result<()> {
+    let delta = i128::from(current_amount) - i128::from(previous_amount);
+    if delta != expected_delta {
+        msg!(
+            "Unexpected balance change: expected {}, got {}",
+            expected_delta,
+            delta
+        );
+        msg!("Old balance: {}", previous_amount);
+        msg!("New balance: {}", current_amount);
+        crate::throw_err!(crate::VipersError::UnexpectedBalanceChange);
+    }
+    Ok(())
+}
+
+/// Reloads a token account via [crate::reload_and_get] and asserts that its amount changed
+/// from `previous_amount` by exactly `expected_delta`, which may be negative.
+///
+/// Throws [crate::VipersError::UnexpectedBalanceChange] otherwise, logging the old and new
+/// amounts and the expected change.
+///
+/// # Example
+///
+/// ```ignore
+/// let previous_amount = ctx.accounts.vault.amount;
+/// token::transfer(cpi_ctx, amount)?;
+/// assert_balance_change!(ctx.accounts.vault, previous_amount, amount);
+/// ```
+#[macro_export]
+macro_rules! assert_balance_change {
+    ($token_account: expr, $previous_amount: expr, $expected_delta: expr $(,)?) => {{
+        let __current_amount = $crate::reload_and_get!($token_account).amount;
+        $crate::spl::check_balance_change(
+            $previous_amount,
+            __current_amount,
+            i128::from($expected_delta),
+        )
+        .map_err(|__err| {
+            msg!("Balance change of {}", stringify!($token_account));
+            __err
+        })?;
+    }};
+}
+
+#[cfg(feature = "token-cpi")]
+pub use cpi::*;
+
+#[cfg(feature = "token-cpi")]
+mod cpi {
+    use super::*;
+    use anchor_spl::token::{self, Burn, Mint, MintTo, TokenAccount, Transfer};
+
+    /// Transfers `amount` tokens from `from` to `to` after running [check_transfer].
+    #[track_caller]
+    pub fn checked_transfer<'info>(
+        token_program: &AccountInfo<'info>,
+        from: &Account<'info, TokenAccount>,
+        to: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<()> {
+        check_transfer(token_program, from, authority, amount).map_err(|err| {
+            msg!("checked_transfer failed: amount {}", amount);
+            msg!("From: {}", from.key());
+            msg!("To: {}", to.key());
+            msg!("Authority: {}", authority.key());
+            err
+        })?;
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                Transfer {
+                    from: from.to_account_info(),
+                    to: to.clone(),
+                    authority: authority.clone(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )
+    }
+
+    /// Mints `amount` tokens of `mint` to `to` after running [check_mint_to].
+    #[track_caller]
+    pub fn checked_mint_to<'info>(
+        token_program: &AccountInfo<'info>,
+        mint: &Account<'info, Mint>,
+        to: &Account<'info, TokenAccount>,
+        authority: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<()> {
+        check_mint_to(token_program, mint, mint, to, authority).map_err(|err| {
+            msg!("checked_mint_to failed: amount {}", amount);
+            msg!("Mint: {}", mint.key());
+            msg!("To: {}", to.key());
+            msg!("Authority: {}", authority.key());
+            err
+        })?;
+        token::mint_to(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                MintTo {
+                    mint: mint.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: authority.clone(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )
+    }
+
+    /// Burns `amount` tokens of `mint` from `from` after running [check_burn].
+    #[track_caller]
+    pub fn checked_burn<'info>(
+        token_program: &AccountInfo<'info>,
+        mint: &Account<'info, Mint>,
+        from: &Account<'info, TokenAccount>,
+        authority: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<()> {
+        check_burn(token_program, mint, from, authority, amount).map_err(|err| {
+            msg!("checked_burn failed: amount {}", amount);
+            msg!("Mint: {}", mint.key());
+            msg!("From: {}", from.key());
+            msg!("Authority: {}", authority.key());
+            err
+        })?;
+        token::burn(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                Burn {
+                    mint: mint.to_account_info(),
+                    from: from.to_account_info(),
+                    authority: authority.clone(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::{assert_does_not_throw, assert_throws, VipersError};
+
+    #[test]
+    fn test_identify_token_program() {
+        assert_eq!(
+            identify_token_program(&crate::program_ids::token::ID).unwrap(),
+            TokenProgramKind::Legacy
+        );
+        assert_eq!(
+            identify_token_program(&crate::program_ids::token_2022::ID).unwrap(),
+            TokenProgramKind::Token2022
+        );
+        assert_throws!(
+            {
+                identify_token_program(&crate::program_ids::system::ID)?;
+            },
+            VipersError::ProgramIDMismatch
+        );
+        assert_eq!(
+            TokenProgramKind::Token2022.program_id(),
+            crate::program_ids::token_2022::ID
+        );
+    }
+
+    #[test]
+    fn test_check_cached_ata() {
+        let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (ata, bump) = derive_ata(&owner, &mint);
+        let token = crate::program_ids::token::ID;
+
+        // cached hit
+        let (result, logs) =
+            crate::testing::capture_logs(|| check_cached_ata(&ata, &ata, &owner, &mint, &token));
+        assert!(result.is_ok());
+        assert!(logs.is_empty());
+
+        // cache miss, with a matching derived ATA
+        let (result, logs) = crate::testing::capture_logs(|| {
+            check_cached_ata(&ata, &Pubkey::default(), &owner, &mint, &token)
+        });
+        assert!(result.is_ok());
+        assert_eq!(
+            logs,
+            vec![format!(
+                "Program log: ATA is not cached: consider caching {} (bump {})",
+                ata, bump
+            )]
+        );
+
+        // mismatches
+        let other = Pubkey::new_unique();
+        assert_throws!(
+            {
+                check_cached_ata(&other, &ata, &owner, &mint, &token)?;
+            },
+            VipersError::ATAMismatch
+        );
+        assert_throws!(
+            {
+                check_cached_ata(&other, &Pubkey::default(), &owner, &mint, &token)?;
+            },
+            VipersError::ATAMismatch
+        );
+        // the cached key is trusted over the derivation
+        assert_throws!(
+            {
+                check_cached_ata(&ata, &other, &owner, &mint, &token)?;
+            },
+            VipersError::ATAMismatch
+        );
+        // the ATA differs across token programs
+        assert_throws!(
+            {
+                assert_cached_ata!(
+                    ata,
+                    Pubkey::default(),
+                    owner,
+                    mint,
+                    token_program = crate::program_ids::token_2022::ID
+                );
+            },
+            VipersError::ATAMismatch
+        );
+        assert_ne!(derive_ata_2022(&owner, &mint).0, ata);
+    }
+
+    #[test]
+    fn test_check_balance_change() {
+        assert_does_not_throw!({
+            check_balance_change(100, 150, 50)?;
+            check_balance_change(100, 40, -60)?;
+            check_balance_change(100, 100, 0)?;
+            check_balance_change(0, u64::MAX, u64::MAX.into())?;
+            check_balance_change(u64::MAX, 0, -i128::from(u64::MAX))?;
+        });
+        assert_throws!(
+            {
+                check_balance_change(100, 150, -50)?;
+            },
+            VipersError::UnexpectedBalanceChange
+        );
+
+        let ((result, line), logs) =
+            crate::testing::capture_logs(|| (check_balance_change(100, 149, 50), line!()));
+        assert!(result.is_err());
+        assert_eq!(
+            logs,
+            vec![
+                "Program log: Unexpected balance change: expected 50, got 49".to_string(),
+                "Program log: Old balance: 100".to_string(),
+                "Program log: New balance: 149".to_string(),
+                format!("Program log: Error thrown at {}:{}", file!(), line),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assert_token_program() {
+        let key = crate::program_ids::token_2022::ID;
+        let mut lamports = 0;
+        let mut data = [];
+        let owner = Pubkey::default();
+        let token_program = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            true,
+            0,
+        );
+        assert_does_not_throw!({
+            crate::assert_token_program!(token_program);
+            crate::assert_token_program!(crate::program_ids::token::ID);
+        });
+        let unrelated = Pubkey::new_unique();
+        assert_throws!(
+            {
+                crate::assert_token_program!(unrelated);
+            },
+            VipersError::ProgramIDMismatch
+        );
+    }
+
+    fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> SplTokenAccount {
+        SplTokenAccount {
+            mint,
+            owner,
+            amount,
+            ..Default::default()
+        }
+    }
+
+    fn mint_with_authority(authority: Pubkey) -> SplMint {
+        SplMint {
+            mint_authority: COption::Some(authority),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_transfer() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let source = token_account(mint, owner, 100);
+        assert_does_not_throw!({
+            check_transfer(&anchor_spl::token::ID, &source, &owner, 100)?;
+        });
+        assert_throws!(
+            {
+                check_transfer(&Pubkey::new_unique(), &source, &owner, 100)?;
+            },
+            VipersError::ProgramIDMismatch
+        );
+        assert_throws!(
+            {
+                check_transfer(&anchor_spl::token::ID, &source, &Pubkey::new_unique(), 100)?;
+            },
+            VipersError::KeyMismatch
+        );
+        assert_throws!(
+            {
+                check_transfer(&anchor_spl::token::ID, &source, &owner, 101)?;
+            },
+            VipersError::InsufficientBalance
+        );
+    }
+
+    #[test]
+    fn test_check_mint_to() {
+        let mint_key = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mint = mint_with_authority(authority);
+        let destination = token_account(mint_key, Pubkey::new_unique(), 0);
+        assert_does_not_throw!({
+            check_mint_to(
+                &anchor_spl::token::ID,
+                &mint_key,
+                &mint,
+                &destination,
+                &authority,
+            )?;
+        });
+        assert_throws!(
+            {
+                check_mint_to(
+                    &Pubkey::new_unique(),
+                    &mint_key,
+                    &mint,
+                    &destination,
+                    &authority,
+                )?;
+            },
+            VipersError::ProgramIDMismatch
+        );
+        assert_throws!(
+            {
+                check_mint_to(
+                    &anchor_spl::token::ID,
+                    &mint_key,
+                    &mint,
+                    &destination,
+                    &Pubkey::new_unique(),
+                )?;
+            },
+            VipersError::KeyMismatch
+        );
+        assert_throws!(
+            {
+                check_mint_to(
+                    &anchor_spl::token::ID,
+                    &mint_key,
+                    &SplMint::default(),
+                    &destination,
+                    &authority,
+                )?;
+            },
+            VipersError::KeyMismatch
+        );
+        assert_throws!(
+            {
+                check_mint_to(
+                    &anchor_spl::token::ID,
+                    &Pubkey::new_unique(),
+                    &mint,
+                    &destination,
+                    &authority,
+                )?;
+            },
+            VipersError::KeyMismatch
+        );
+    }
+
+    #[test]
+    fn test_check_burn() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let source = token_account(mint, owner, 100);
+        assert_does_not_throw!({
+            check_burn(&anchor_spl::token::ID, &mint, &source, &owner, 100)?;
+        });
+        assert_throws!(
+            {
+                check_burn(&Pubkey::new_unique(), &mint, &source, &owner, 100)?;
+            },
+            VipersError::ProgramIDMismatch
+        );
+        assert_throws!(
+            {
+                check_burn(
+                    &anchor_spl::token::ID,
+                    &Pubkey::new_unique(),
+                    &source,
+                    &owner,
+                    100,
+                )?;
+            },
+            VipersError::KeyMismatch
+        );
+        assert_throws!(
+            {
+                check_burn(
+                    &anchor_spl::token::ID,
+                    &mint,
+                    &source,
+                    &Pubkey::new_unique(),
+                    100,
+                )?;
+            },
+            VipersError::KeyMismatch
+        );
+        assert_throws!(
+            {
+                check_burn(&anchor_spl::token::ID, &mint, &source, &owner, 101)?;
+            },
+            VipersError::InsufficientBalance
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Diagnostics for stack usage.
+//!
+//! Solana programs have a fixed-size stack frame per function call, and overflowing it
+//! often only manifests as an access violation at runtime. The [crate::log_stack_usage]
+//! and [crate::assert_stack_headroom] macros help find where this happens.
+//!
+//! These macros are only active with the `debug-stack` feature on the `bpf` target.
+//! Otherwise, they compile to nothing and do not evaluate their arguments.
+
+/// Size of a single BPF stack frame, in bytes.
+pub const STACK_FRAME_SIZE: usize = 4096;
+
+/// Returns the number of bytes remaining in the current stack frame.
+///
+/// BPF stack frames are [STACK_FRAME_SIZE]-aligned and grow downwards, so the offset of a
+/// local within its frame is the number of bytes still available below it.
+/// This is inlined so that it measures the frame of the caller.
+#[cfg(target_arch = "bpf")]
+#[inline(always)]
+pub fn stack_headroom() -> usize {
+    let marker = 0_u8;
+    (&marker as *const u8 as usize) % STACK_FRAME_SIZE
+}
+
+/// Logs the number of bytes used and remaining in the current stack frame.
+///
+/// Requires the `debug-stack` feature; otherwise this is a no-op.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() {
+/// log_stack_usage!("before swap");
+/// # }
+/// ```
+#[cfg(all(feature = "debug-stack", target_arch = "bpf"))]
+#[macro_export]
+macro_rules! log_stack_usage {
+    ($label: expr $(,)?) => {{
+        let __headroom = $crate::stack::stack_headroom();
+        msg!(
+            "Stack usage at {}: {} bytes used, {} bytes remaining",
+            $label,
+            $crate::stack::STACK_FRAME_SIZE - __headroom,
+            __headroom
+        );
+    }};
+}
+
+/// Logs the number of bytes used and remaining in the current stack frame.
+///
+/// Requires the `debug-stack` feature; otherwise this is a no-op.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() {
+/// log_stack_usage!("before swap");
+/// # }
+/// ```
+#[cfg(not(all(feature = "debug-stack", target_arch = "bpf")))]
+#[macro_export]
+macro_rules! log_stack_usage {
+    ($label: expr $(,)?) => {};
+}
+
+/// Asserts that at least the given number of bytes remain in the current stack frame,
+/// throwing [crate::VipersError::InsufficientStackHeadroom] otherwise.
+///
+/// Requires the `debug-stack` feature; otherwise this is a no-op.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() -> Result<()> {
+/// assert_stack_headroom!(1024);
+/// # Ok(()) }
+/// ```
+#[cfg(all(feature = "debug-stack", target_arch = "bpf"))]
+#[macro_export]
+macro_rules! assert_stack_headroom {
+    ($bytes: expr $(,)?) => {{
+        let __headroom = $crate::stack::stack_headroom();
+        let __required: usize = $bytes;
+        if __headroom < __required {
+            msg!(
+                "Insufficient stack headroom: {} bytes remaining, {} required",
+                __headroom,
+                __required
+            );
+            $crate::throw_err!($crate::VipersError::InsufficientStackHeadroom);
+        }
+    }};
+}
+
+/// Asserts that at least the given number of bytes remain in the current stack frame,
+/// throwing [crate::VipersError::InsufficientStackHeadroom] otherwise.
+///
+/// Requires the `debug-stack` feature; otherwise this is a no-op.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() -> Result<()> {
+/// assert_stack_headroom!(1024);
+/// # Ok(()) }
+/// ```
+#[cfg(not(all(feature = "debug-stack", target_arch = "bpf")))]
+#[macro_export]
+macro_rules! assert_stack_headroom {
+    ($bytes: expr $(,)?) => {};
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+#[cfg(not(all(feature = "debug-stack", target_arch = "bpf")))]
+mod tests {
+    use crate::testing::capture_logs;
+
+    #[test]
+    fn test_no_op() {
+        let (result, logs) = capture_logs(|| -> anchor_lang::Result<()> {
+            log_stack_usage!({
+                panic!("the label should not be evaluated");
+                #[allow(unreachable_code)]
+                "label"
+            });
+            assert_stack_headroom!({
+                panic!("the byte count should not be evaluated");
+                #[allow(unreachable_code)]
+                0
+            });
+            assert_stack_headroom!(usize::MAX);
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert!(logs.is_empty());
+    }
+}
@@ -0,0 +1,406 @@
+//! Host-side helpers for writing tests against serialized account data and program logs.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_stubs::{self, SyscallStubs};
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+/// Length of an Anchor account discriminator.
+const DISCRIMINATOR_LEN: usize = 8;
+
+/// Length of a [Pubkey].
+const PUBKEY_LEN: usize = 32;
+
+/// Renders a human-readable diff of two serialized accounts.
+///
+/// The data is split into the 8-byte Anchor discriminator followed by 32-byte chunks.
+/// Only chunks which differ are rendered. Each chunk is annotated with its offset, a hex
+/// and ASCII dump of both sides, and markers under the differing bytes. Full 32-byte chunks
+/// are also rendered as [Pubkey]s, since this is the most common 32-byte field.
+///
+/// # Example
+///
+/// ```
+/// let diff = vipers::testing::pretty_account_diff(&[1, 2, 3], &[1, 2, 4]);
+/// assert!(diff.contains("discriminator"));
+/// ```
+pub fn pretty_account_diff(expected: &[u8], actual: &[u8]) -> String {
+    let mut out = String::new();
+    let max_len = expected.len().max(actual.len());
+    let min_len = expected.len().min(actual.len());
+
+    if expected == actual {
+        writeln!(out, "account data is identical ({} bytes)", expected.len()).unwrap();
+        return out;
+    }
+    writeln!(
+        out,
+        "account data differs: expected {} bytes, actual {} bytes",
+        expected.len(),
+        actual.len()
+    )
+    .unwrap();
+
+    let mut start = 0;
+    while start < max_len {
+        let end = if start < DISCRIMINATOR_LEN {
+            DISCRIMINATOR_LEN
+        } else {
+            start + PUBKEY_LEN
+        }
+        .min(max_len);
+
+        let expected_chunk = chunk(expected, start, end);
+        let actual_chunk = chunk(actual, start, end);
+        if expected_chunk != actual_chunk {
+            let mut label = if start == 0 {
+                "discriminator".to_string()
+            } else if end - start == PUBKEY_LEN {
+                "potential pubkey".to_string()
+            } else {
+                format!("{} bytes", end - start)
+            };
+            if end > min_len {
+                label.push_str(", trailing bytes");
+            }
+            writeln!(out, "[{:#06x}..{:#06x}] {}", start, end, label).unwrap();
+            writeln!(out, "  - {}", render_row(expected_chunk, end - start)).unwrap();
+            writeln!(out, "  + {}", render_row(actual_chunk, end - start)).unwrap();
+            writeln!(
+                out,
+                "    {}",
+                render_markers(expected_chunk, actual_chunk, end - start)
+            )
+            .unwrap();
+            if start >= DISCRIMINATOR_LEN && end - start == PUBKEY_LEN {
+                writeln!(out, "  - pubkey: {}", render_pubkey(expected_chunk)).unwrap();
+                writeln!(out, "  + pubkey: {}", render_pubkey(actual_chunk)).unwrap();
+            }
+        }
+        start = end;
+    }
+    out
+}
+
+/// Returns the bytes of `data` in `start..end`, truncated to the length of `data`.
+fn chunk(data: &[u8], start: usize, end: usize) -> &[u8] {
+    &data[start.min(data.len())..end.min(data.len())]
+}
+
+/// Renders a hex and ASCII dump of a chunk, padded to `width` bytes.
+fn render_row(bytes: &[u8], width: usize) -> String {
+    let hex: Vec<String> = (0..width)
+        .map(|i| match bytes.get(i) {
+            Some(b) => format!("{:02x}", b),
+            None => "--".to_string(),
+        })
+        .collect();
+    let ascii: String = bytes
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    format!("{}  |{}|", hex.join(" "), ascii)
+}
+
+/// Renders `^^` markers under each differing byte.
+fn render_markers(expected: &[u8], actual: &[u8], width: usize) -> String {
+    let markers: Vec<&str> = (0..width)
+        .map(|i| {
+            if expected.get(i) == actual.get(i) {
+                "  "
+            } else {
+                "^^"
+            }
+        })
+        .collect();
+    markers.join(" ").trim_end().to_string()
+}
+
+/// Renders a chunk as a [Pubkey] if it is exactly 32 bytes long.
+fn render_pubkey(bytes: &[u8]) -> String {
+    if bytes.len() == PUBKEY_LEN {
+        Pubkey::new_from_array(bytes.try_into().unwrap()).to_string()
+    } else {
+        "<missing>".to_string()
+    }
+}
+
+thread_local! {
+    /// Logs captured on the current thread, if [capture_logs] is running.
+    static CAPTURED_LOGS: RefCell<Option<Vec<String>>> = RefCell::new(None);
+}
+
+static INSTALL_TEST_STUBS: Once = Once::new();
+
+/// Whether [install_rent_stub] has been called.
+static RENT_STUB_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Syscall stubs which record logs into [CAPTURED_LOGS].
+///
+/// Once [install_rent_stub] is called, these also provide [Rent::default] as the [Rent] sysvar.
+struct TestStubs;
+
+/// Replaces the global syscall stubs with [TestStubs], if this has not happened yet.
+fn install_test_stubs() {
+    INSTALL_TEST_STUBS.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(TestStubs));
+    });
+}
+
+impl SyscallStubs for TestStubs {
+    fn sol_log(&self, message: &str) {
+        record_log(format!("Program log: {}", message));
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields: Vec<String> = fields.iter().map(|field| base64_encode(field)).collect();
+        record_log(format!("Program data: {}", fields.join(" ")));
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        if !RENT_STUB_INSTALLED.load(Ordering::SeqCst) {
+            return anchor_lang::solana_program::program_error::UNSUPPORTED_SYSVAR;
+        }
+        // SAFETY: the runtime passes a pointer to a `Rent`.
+        unsafe {
+            *(var_addr as *mut Rent) = Rent::default();
+        }
+        anchor_lang::solana_program::entrypoint::SUCCESS
+    }
+}
+
+/// Records a log line, or prints it if logs are not being captured.
+fn record_log(line: String) {
+    CAPTURED_LOGS.with(|logs| match logs.borrow_mut().as_mut() {
+        Some(logs) => logs.push(line),
+        None => println!("{}", line),
+    });
+}
+
+/// Encodes bytes as standard, padded base64, as used in `Program data:` logs.
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for group in data.chunks(3) {
+        let b = [
+            group[0],
+            *group.get(1).unwrap_or(&0),
+            *group.get(2).unwrap_or(&0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= group.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Runs `f`, returning its result along with the logs it emitted on the current thread.
+///
+/// Logs are formatted as they appear in transaction logs: `Program log: ...` for [msg]
+/// and `Program data: ...` for `sol_log_data`, which is used by `emit!`.
+///
+/// On first use, this replaces the global syscall stubs via [program_stubs::set_syscall_stubs].
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// let (_, logs) = vipers::testing::capture_logs(|| msg!("hello"));
+/// assert_eq!(logs, vec!["Program log: hello".to_string()]);
+/// ```
+pub fn capture_logs<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    install_test_stubs();
+    let previous = CAPTURED_LOGS.with(|logs| logs.replace(Some(vec![])));
+    let result = f();
+    let logs = CAPTURED_LOGS
+        .with(|logs| logs.replace(previous))
+        .unwrap_or_default();
+    (result, logs)
+}
+
+/// Makes [Rent::get] return [Rent::default] for the rest of the test process.
+///
+/// Without this, [Rent::get] fails off-chain with [ProgramError::UnsupportedSysvar].
+/// Like [capture_logs], this replaces the global syscall stubs on first use, and
+/// both may be used together.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// vipers::testing::install_rent_stub();
+/// assert_eq!(Rent::get().unwrap(), Rent::default());
+/// ```
+pub fn install_rent_stub() {
+    install_test_stubs();
+    RENT_STUB_INSTALLED.store(true, Ordering::SeqCst);
+}
+
+/// Runs `f` with a writable, non-signer [AccountInfo] owned by `owner` and holding `data`.
+///
+/// The account has a fresh key from [Pubkey::new_unique] and no lamports. Nest calls
+/// to build several accounts.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// let owner = Pubkey::new_unique();
+/// let len = vipers::testing::with_account(&owner, vec![1, 2, 3], |info| {
+///     assert_eq!(info.owner, &owner);
+///     info.data_len()
+/// });
+/// assert_eq!(len, 3);
+/// ```
+pub fn with_account<R>(
+    owner: &Pubkey,
+    mut data: Vec<u8>,
+    f: impl for<'info> FnOnce(&AccountInfo<'info>) -> R,
+) -> R {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, owner, false, 0);
+    f(&info)
+}
+
+/// Asserts that two serialized accounts are byte-for-byte equal,
+/// panicking with a [pretty_account_diff] if they are not.
+///
+/// Recommended for use in tests only.
+///
+/// # Example
+///
+/// ```should_panic
+/// # #[macro_use] extern crate vipers;
+/// # fn main() {
+/// assert_account_bytes_eq!([1_u8, 2, 3], [1_u8, 2, 4]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_account_bytes_eq {
+    ($expected: expr, $actual: expr $(,)?) => {
+        match (&$expected, &$actual) {
+            (__expected, __actual) => {
+                let __expected: &[u8] = ::core::convert::AsRef::<[u8]>::as_ref(__expected);
+                let __actual: &[u8] = ::core::convert::AsRef::<[u8]>::as_ref(__actual);
+                if __expected != __actual {
+                    panic!(
+                        "{} != {}\n{}",
+                        stringify!($expected),
+                        stringify!($actual),
+                        $crate::testing::pretty_account_diff(__expected, __actual)
+                    );
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        let mut data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        data.extend_from_slice(&Pubkey::new_unique().to_bytes());
+        data.extend_from_slice(&Pubkey::new_unique().to_bytes());
+        data.extend_from_slice(b"hello");
+        data
+    }
+
+    #[test]
+    fn test_identical() {
+        let data = sample();
+        let diff = pretty_account_diff(&data, &data);
+        assert!(diff.contains("identical"));
+        assert_account_bytes_eq!(data, data.clone());
+    }
+
+    #[test]
+    fn test_discriminator_diff() {
+        let expected = sample();
+        let mut actual = expected.clone();
+        actual[7] = 0xff;
+        let diff = pretty_account_diff(&expected, &actual);
+        assert!(diff.contains("[0x0000..0x0008] discriminator"));
+        assert!(diff.contains("  - 01 02 03 04 05 06 07 08"));
+        assert!(diff.contains("  + 01 02 03 04 05 06 07 ff"));
+        assert!(diff.contains(&format!("    {}^^", " ".repeat(3 * 7))));
+        assert!(!diff.contains("potential pubkey"));
+    }
+
+    #[test]
+    fn test_pubkey_diff() {
+        let expected = sample();
+        let mut actual = expected.clone();
+        let replacement = Pubkey::new_unique();
+        actual[40..72].copy_from_slice(&replacement.to_bytes());
+        let diff = pretty_account_diff(&expected, &actual);
+        assert!(!diff.contains("discriminator"));
+        assert!(diff.contains("[0x0028..0x0048] potential pubkey"));
+        assert!(diff.contains(&format!(
+            "  - pubkey: {}",
+            Pubkey::new_from_array(expected[40..72].try_into().unwrap())
+        )));
+        assert!(diff.contains(&format!("  + pubkey: {}", replacement)));
+    }
+
+    #[test]
+    fn test_trailing_diff() {
+        let expected = sample();
+        let mut actual = expected.clone();
+        actual.extend_from_slice(b"!!");
+        let diff = pretty_account_diff(&expected, &actual);
+        assert!(diff.contains("expected 77 bytes, actual 79 bytes"));
+        assert!(diff.contains("[0x0048..0x004f] 7 bytes, trailing bytes"));
+        assert!(diff.contains("  - 68 65 6c 6c 6f -- --  |hello|"));
+        assert!(diff.contains("  + 68 65 6c 6c 6f 21 21  |hello!!|"));
+        assert!(diff.contains(&format!("    {}^^ ^^", " ".repeat(3 * 5))));
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_capture_logs() {
+        let (result, logs) = capture_logs(|| {
+            msg!("one");
+            anchor_lang::solana_program::log::sol_log_data(&[b"foo", b"fo"]);
+            1
+        });
+        assert_eq!(result, 1);
+        assert_eq!(logs, vec!["Program log: one", "Program data: Zm9v Zm8="]);
+
+        let (_, logs) = capture_logs(|| {});
+        assert!(logs.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "potential pubkey")]
+    fn test_assert_account_bytes_eq_panics() {
+        let expected = sample();
+        let mut actual = expected.clone();
+        actual[8] ^= 1;
+        assert_account_bytes_eq!(expected, actual);
+    }
+}
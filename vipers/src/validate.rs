@@ -1,4 +1,9 @@
 //! Validations for accounts.
+//!
+//! Structural checks which only depend on the accounts (ownership, key relationships, etc.)
+//! belong in [Validate::validate]. Checks which also depend on the instruction arguments
+//! (e.g. `amount <= vault.amount`) belong in [ValidateArgs::validate_args].
+//! Use [crate::validate_with_args] to run both, in that order.
 
 use anchor_lang::prelude::*;
 
@@ -7,3 +12,38 @@ pub trait Validate<'info> {
     /// Validates the account struct.
     fn validate(&self) -> Result<()>;
 }
+
+/// Validates the contents of an [Accounts] struct against the instruction arguments `A`.
+pub trait ValidateArgs<'info, A> {
+    /// Validates the account struct against the instruction arguments.
+    fn validate_args(&self, args: &A) -> Result<()>;
+}
+
+/// Runs [Validate::validate] followed by [ValidateArgs::validate_args] on the accounts
+/// of the given [Context], logging which of the two failed.
+///
+/// # Example
+///
+/// ```ignore
+/// pub fn deposit(ctx: Context<Deposit>, args: DepositArgs) -> Result<()> {
+///     validate_with_args!(ctx, &args);
+///     // ...
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! validate_with_args {
+    ($ctx: expr, $args: expr $(,)?) => {{
+        let __accounts = &*$ctx.accounts;
+        $crate::Validate::validate(__accounts).map_err(|__err| {
+            msg!("Account validation failed: {}", stringify!($ctx));
+            $crate::log_code_location!();
+            __err
+        })?;
+        $crate::ValidateArgs::validate_args(__accounts, $args).map_err(|__err| {
+            msg!("Argument validation failed: {}", stringify!($args));
+            $crate::log_code_location!();
+            __err
+        })?;
+    }};
+}
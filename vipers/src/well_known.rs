@@ -0,0 +1,89 @@
+//! Well-known keys.
+
+use crate::program_ids;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable, sysvar,
+};
+
+/// Keys which can never spend funds sent to them, along with their names.
+///
+/// Funds sent to any of these keys are unrecoverable.
+/// The system program ID is [Pubkey::default()].
+#[allow(deprecated)]
+pub static UNSPENDABLE_KEYS: &[(&str, &Pubkey)] = &[
+    ("system program (default pubkey)", &program_ids::system::ID),
+    ("token program", &program_ids::token::ID),
+    ("token 2022 program", &program_ids::token_2022::ID),
+    (
+        "associated token program",
+        &program_ids::associated_token::ID,
+    ),
+    ("BPF loader", &bpf_loader::ID),
+    ("deprecated BPF loader", &bpf_loader_deprecated::ID),
+    ("upgradeable BPF loader", &bpf_loader_upgradeable::ID),
+    ("sysvar owner", &sysvar::ID),
+    ("clock sysvar", &sysvar::clock::ID),
+    ("epoch schedule sysvar", &sysvar::epoch_schedule::ID),
+    ("fees sysvar", &sysvar::fees::ID),
+    ("instructions sysvar", &sysvar::instructions::ID),
+    ("recent blockhashes sysvar", &sysvar::recent_blockhashes::ID),
+    ("rent sysvar", &sysvar::rent::ID),
+    ("rewards sysvar", &sysvar::rewards::ID),
+    ("slot hashes sysvar", &sysvar::slot_hashes::ID),
+    ("slot history sysvar", &sysvar::slot_history::ID),
+    ("stake history sysvar", &sysvar::stake_history::ID),
+];
+
+/// Returns the name of the [UNSPENDABLE_KEYS] entry matching the given key, if any.
+pub fn find_unspendable(key: &Pubkey) -> Option<&'static str> {
+    UNSPENDABLE_KEYS
+        .iter()
+        .find(|(_, unspendable)| crate::keys_eq(key, unspendable))
+        .map(|(name, _)| *name)
+}
+
+/// Asserts that a key is not one of the [UNSPENDABLE_KEYS], nor one of the optional `extra` keys.
+///
+/// This is useful for validating user-supplied destinations of funds.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() -> Result<()> {
+/// let old_treasury = Pubkey::new_unique();
+/// let destination = Pubkey::new_unique();
+/// assert_not_unspendable!(destination);
+/// assert_not_unspendable!(destination, extra: [old_treasury]);
+/// # Ok(()) }
+/// ```
+///
+/// ```should_panic
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate vipers; fn main() -> Result<()> {
+/// assert_not_unspendable!(Pubkey::default()); // throws an error
+/// # Ok(()) }
+/// ```
+#[macro_export]
+macro_rules! assert_not_unspendable {
+    ($destination: expr $(,)?) => {
+        $crate::assert_not_unspendable!($destination, extra: [])
+    };
+    ($destination: expr, extra: [$($extra: expr),* $(,)?] $(,)?) => {{
+        let __destination = &$destination;
+        let __key = $crate::AsKeyRef::as_key_ref(__destination);
+        if let Some(__name) = $crate::well_known::find_unspendable(__key) {
+            msg!("Unspendable destination: {} is the {}", __key, __name);
+            msg!(stringify!($destination));
+            $crate::throw_err!($crate::VipersError::UnspendableDestination);
+        }
+        $(
+            if $crate::keys_eq(__key, $crate::AsKeyRef::as_key_ref(&$extra)) {
+                msg!("Unspendable destination: {} is {}", __key, stringify!($extra));
+                msg!(stringify!($destination));
+                $crate::throw_err!($crate::VipersError::UnspendableDestination);
+            }
+        )*
+    }};
+}